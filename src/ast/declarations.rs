@@ -8,6 +8,7 @@ use salsa::DebugWithDb;
 
 pub type Module = Located<Commented<ModuleInner>>;
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Eq, PartialEq, Debug, Hash, Clone, DebugWithDb)]
 pub struct ModuleInner {
     pub name: ModuleId,
@@ -28,6 +29,7 @@ pub fn corrupted(db: &dyn Db, span: SourceSpan) -> Module {
 
 pub type Import = Located<ImportInner>;
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Eq, PartialEq, Debug, Hash, Clone, DebugWithDb)]
 pub struct ImportInner {
     pub module: ModuleId,
@@ -37,12 +39,14 @@ pub struct ImportInner {
 
 pub type DeclarationRef = Located<DeclarationRefKind>;
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Eq, PartialEq, Debug, Hash, Clone, DebugWithDb)]
 pub enum NameSource {
     UserNamed,
     CompilerNamed,
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Eq, PartialEq, Debug, Hash, Clone, DebugWithDb)]
 pub enum DeclarationRefKind {
     TypeClass {
@@ -70,12 +74,14 @@ pub enum DeclarationRefKind {
     },
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Eq, PartialEq, Debug, Hash, Clone, DebugWithDb)]
 pub enum DeclarationRefConstructors {
     All,
     Some(Vec<Symbol>),
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Eq, PartialEq, Debug, Hash, Clone, DebugWithDb)]
 pub enum ImportDeclarationKind {
     Implicit,
@@ -83,12 +89,14 @@ pub enum ImportDeclarationKind {
     Hiding(Vec<DeclarationRef>),
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Eq, PartialEq, Debug, Hash, Clone, DebugWithDb)]
 pub struct RoleDeclarationData {
     pub ident: Symbol,
     pub role: Vec<Role>,
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Eq, PartialEq, Debug, Hash, Clone, DebugWithDb)]
 pub enum Role {
     Nominal,
@@ -97,6 +105,7 @@ pub enum Role {
 }
 
 #[derive(Eq, PartialEq, Debug, Hash, Clone, DebugWithDb)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 // TODO: rename to TypeSignatureData or something?
 pub struct TypeDeclarationData {
     pub ident: Symbol,
@@ -111,6 +120,7 @@ impl TypeDeclarationData {
 
 pub type Declaration = Located<Commented<DeclarationKind>>;
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Eq, PartialEq, Debug, Hash, Clone, DebugWithDb)]
 pub enum DeclarationKind {
     Data {
@@ -143,6 +153,7 @@ pub enum DeclarationKind {
     Destructuring {
         pat: Pat,
         expr: PossiblyGuardedExpr,
+        where_: Vec<Declaration>,
     },
 
     ForeignValue {
@@ -162,6 +173,10 @@ pub enum DeclarationKind {
     },
 }
 
+/// Highest precedence allowed in a fixity declaration.
+pub const MAX_PRECEDENCE: u8 = 10;
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Eq, PartialEq, Debug, Hash, Clone, DebugWithDb)]
 pub enum Associativity {
     None,
@@ -169,6 +184,7 @@ pub enum Associativity {
     Right,
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Eq, PartialEq, Debug, Hash, Clone, DebugWithDb)]
 pub enum OperatorTarget {
     Type(QualifiedName),
@@ -180,6 +196,7 @@ pub type Kind = Type;
 
 pub type TypeParameter = (Symbol, Option<Kind>);
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Eq, PartialEq, Debug, Hash, Clone, DebugWithDb)]
 pub struct TypeClassDeclaration {
     pub constraints: Vec<Type>,
@@ -189,6 +206,7 @@ pub struct TypeClassDeclaration {
     pub methods: Vec<TypeDeclarationData>,
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Eq, PartialEq, Debug, Hash, Clone, DebugWithDb)]
 pub struct InstanceDeclaration {
     pub constraints: Vec<Type>,
@@ -199,28 +217,37 @@ pub struct InstanceDeclaration {
     pub body: Vec<Declaration>,
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Eq, PartialEq, Debug, Hash, Clone, DebugWithDb)]
 pub struct Fundep {
     pub from: Vec<Symbol>,
     pub to: Vec<Symbol>,
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Eq, PartialEq, Debug, Hash, Clone, DebugWithDb)]
 pub enum InstanceType {
+    /// `instance`, with methods given in the body
     Plain,
+    /// `derive instance`, generated from the structure of the type
     Derive,
+    /// `derive newtype instance`, reusing the instance of the type the newtype wraps
     DeriveNewtype,
 }
 
 // Note: `data` and `newtype` signatures are actually declarations without constructors
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Eq, PartialEq, Debug, Hash, Clone, DebugWithDb, Copy)]
 pub enum KindSignatureFor {
+    Data,
+    Newtype,
     TypeSynonym,
     Class,
 }
 
 pub type DataConstructorDeclaration = Located<Commented<DataConstructorDeclarationData>>;
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Eq, PartialEq, Debug, Hash, Clone, DebugWithDb)]
 pub struct DataConstructorDeclarationData {
     pub name: Symbol,
@@ -229,6 +256,7 @@ pub struct DataConstructorDeclarationData {
     pub fields: Vec<Type>,
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Eq, PartialEq, Debug, Hash, Clone, DebugWithDb, Copy)]
 pub enum DataDeclType {
     Data,
@@ -236,9 +264,12 @@ pub enum DataDeclType {
     Newtype,
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Eq, PartialEq, Debug, Hash, Clone, DebugWithDb)]
 pub struct ValueDeclaration {
     pub ident: Symbol,
     pub params: Vec<Pat>,
     pub expr: PossiblyGuardedExpr,
+    /// Declarations of the `where` block, in scope in all guards and right-hand sides.
+    pub where_: Vec<Declaration>,
 }
@@ -4,11 +4,13 @@ use crate::ast::QualifiedName;
 use crate::string::PSChar;
 use crate::string::PSString;
 use crate::symbol::Symbol;
+use crate::ModuleId;
 use ordered_float::OrderedFloat;
 use salsa::DebugWithDb;
 
 pub type Expr = Located<ExprKind>;
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Eq, PartialEq, Debug, Hash, Clone, DebugWithDb)]
 pub enum ExprKind {
     Literal(Literal<Expr>),
@@ -45,6 +47,9 @@ pub enum ExprKind {
 
     Typed(Box<Expr>, Box<Type>),
 
+    /// Visible type application, `f @Type`
+    TypeApp(Box<Expr>, Box<Type>),
+
     Let {
         decls: Vec<Declaration>,
         body: Box<Expr>,
@@ -52,28 +57,42 @@ pub enum ExprKind {
 
     Wildcard,
 
+    /// Typed hole, e.g. `?help`
+    Hole(Symbol),
+
     // Pseudo-expression, used only as an intermediate value during parsing.
     RecordUpdateSuffix(RecordUpdate),
 
     // Pseudo-expression, used only as an intermediate value during parsing.
     NamedPat(Symbol, Box<Expr>),
 
-    Do(Vec<DoItem>),
+    /// `do` block, with the module alias of a qualified `M.do`
+    Do(Option<ModuleId>, Vec<DoItem>),
 
-    Ado(Vec<DoItem>, Box<Expr>),
+    /// `ado` block, with the module alias of a qualified `M.ado`
+    Ado(Option<ModuleId>, Vec<DoItem>, Box<Expr>),
 
     Negate(Box<Expr>),
 
+    /// Parenthesized operator section with `_` placeholders, e.g. `(_ + 1)`, or a `case` with `_`
+    /// scrutinees. The inner expression is the `Infix` chain, application or `Case`; see
+    /// `parser::desugar_section`.
+    Section(Box<Expr>),
+
     /// Invalid expression (but we still proceed around it)
     Error,
 }
 
+pub type InfixOp = Located<InfixOpKind>;
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Eq, PartialEq, Debug, Hash, Clone, DebugWithDb)]
-pub enum InfixOp {
+pub enum InfixOpKind {
     Symbol(QualifiedName),
     Backtick(Box<Expr>),
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Eq, PartialEq, Debug, Hash, Clone, DebugWithDb)]
 pub enum DoItem {
     Let(Vec<Declaration>),
@@ -83,16 +102,19 @@ pub enum DoItem {
 
 type RecordUpdate = Vec<(Symbol, Expr)>;
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Eq, PartialEq, Debug, Hash, Clone, DebugWithDb)]
 pub enum RecordLiteralOrUpdate {
     Literal(Vec<(Symbol, Expr)>),
     Update(Vec<(Symbol, Expr)>),
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Eq, PartialEq, Debug, Hash, Clone, DebugWithDb)]
 pub struct CaseBranch {
     pub pats: Vec<Pat>,
     pub expr: PossiblyGuardedExpr,
+    pub where_: Vec<Declaration>,
 }
 
 impl From<ValueDeclaration> for CaseBranch {
@@ -100,22 +122,26 @@ impl From<ValueDeclaration> for CaseBranch {
         Self {
             pats: value.params,
             expr: value.expr,
+            where_: value.where_,
         }
     }
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Eq, PartialEq, Debug, Hash, Clone, DebugWithDb)]
 pub enum PossiblyGuardedExpr {
     Unconditional(Expr),
     Guarded(Vec<GuardedExpr>),
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Eq, PartialEq, Debug, Hash, Clone, DebugWithDb)]
 pub struct GuardedExpr {
     pub guards: Vec<Guard>,
     pub expr: Expr,
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Eq, PartialEq, Debug, Hash, Clone, DebugWithDb)]
 pub enum Guard {
     Expr(Expr),
@@ -124,6 +150,7 @@ pub enum Guard {
 
 pub type Pat = Located<PatKind>;
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Eq, PartialEq, Debug, Hash, Clone, DebugWithDb)]
 pub enum PatKind {
     Literal(Literal<Pat>),
@@ -133,7 +160,8 @@ pub enum PatKind {
 
     Var(Symbol),
 
-    DataConstructorApp(QualifiedName, Vec<Pat>),
+    /// Constructor with its visible type arguments (`Proxy @Int`) and argument patterns
+    DataConstructorApp(QualifiedName, Vec<Type>, Vec<Pat>),
 
     Wildcard,
 
@@ -141,9 +169,13 @@ pub enum PatKind {
 
     Typed(Box<Pat>, Box<Type>),
 
+    /// Record pattern; a field without a pattern is a pun, binding a variable named like the field
+    Record(Vec<(Symbol, Option<Pat>)>),
+
     Error,
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Eq, PartialEq, Debug, Hash, Clone, DebugWithDb)]
 pub enum Literal<T> {
     Integer(i64),
@@ -2,6 +2,7 @@ use salsa::DebugWithDb;
 
 use crate::{renamed_module::DeclId, source_span::*};
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Eq, PartialEq, Debug, Hash, Clone, DebugWithDb)]
 pub struct Located<T>(pub SourceSpan, pub T);
 
@@ -43,9 +44,21 @@ impl<T> std::ops::DerefMut for Located<T> {
     }
 }
 
+/// The text of a comment, without its delimiters.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Eq, PartialEq, Debug, Hash, Clone, DebugWithDb)]
-pub struct Comment(pub String);
+pub struct Comment(pub String, pub CommentKind);
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Eq, PartialEq, Debug, Hash, Clone, Copy, DebugWithDb)]
+pub enum CommentKind {
+    Ordinary,
+    /// A documentation comment, `-- |` or `{- | -}`. Its text is everything after the `|`, kept
+    /// verbatim.
+    Doc,
+}
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Eq, PartialEq, Debug, Hash, Clone, DebugWithDb)]
 pub struct Commented<T>(pub Vec<Comment>, pub T);
 
@@ -0,0 +1,203 @@
+//! Size and shape statistics of a module, for testing and benchmarking passes over the AST.
+
+use super::visit::{walk_declaration, walk_expr, walk_pat, walk_type, Visitor};
+use super::*;
+use std::collections::BTreeMap;
+
+#[derive(Eq, PartialEq, Debug, Clone, Default)]
+pub struct AstMetrics {
+    /// Number of declarations, expressions, patterns and types
+    pub nodes: usize,
+    /// Length of the longest chain of nested nodes. A module with only `x = 1` has depth 2.
+    pub max_depth: usize,
+    /// Number of nodes of each kind, keyed by the variant name
+    pub declarations: BTreeMap<&'static str, usize>,
+    pub exprs: BTreeMap<&'static str, usize>,
+    pub pats: BTreeMap<&'static str, usize>,
+    pub types: BTreeMap<&'static str, usize>,
+}
+
+pub fn ast_metrics(module: &Module) -> AstMetrics {
+    let mut collector = MetricsCollector::default();
+    collector.visit_module(module);
+    collector.metrics
+}
+
+#[derive(Default)]
+struct MetricsCollector {
+    metrics: AstMetrics,
+    depth: usize,
+}
+
+impl MetricsCollector {
+    fn enter(&mut self) {
+        self.metrics.nodes += 1;
+        self.depth += 1;
+        self.metrics.max_depth = self.metrics.max_depth.max(self.depth);
+    }
+
+    fn exit(&mut self) {
+        self.depth -= 1;
+    }
+}
+
+impl Visitor for MetricsCollector {
+    fn visit_declaration(&mut self, decl: &Declaration) {
+        *self
+            .metrics
+            .declarations
+            .entry(declaration_kind_name(&decl.1 .1))
+            .or_default() += 1;
+        self.enter();
+        walk_declaration(self, decl);
+        self.exit();
+    }
+
+    fn visit_expr(&mut self, expr: &Expr) {
+        *self
+            .metrics
+            .exprs
+            .entry(expr_kind_name(&expr.1))
+            .or_default() += 1;
+        self.enter();
+        walk_expr(self, expr);
+        self.exit();
+    }
+
+    fn visit_pat(&mut self, pat: &Pat) {
+        *self.metrics.pats.entry(pat_kind_name(&pat.1)).or_default() += 1;
+        self.enter();
+        walk_pat(self, pat);
+        self.exit();
+    }
+
+    fn visit_type(&mut self, type_: &Type) {
+        *self
+            .metrics
+            .types
+            .entry(type_kind_name(&type_.1))
+            .or_default() += 1;
+        self.enter();
+        walk_type(self, type_);
+        self.exit();
+    }
+}
+
+fn declaration_kind_name(kind: &DeclarationKind) -> &'static str {
+    match kind {
+        DeclarationKind::Data { .. } => "Data",
+        DeclarationKind::TypeSynonym { .. } => "TypeSynonym",
+        DeclarationKind::KindSignature { .. } => "KindSignature",
+        DeclarationKind::Role(_) => "Role",
+        DeclarationKind::TypeSignature(_) => "TypeSignature",
+        DeclarationKind::ValueDeclaration(_) => "ValueDeclaration",
+        DeclarationKind::Destructuring { .. } => "Destructuring",
+        DeclarationKind::ForeignValue { .. } => "ForeignValue",
+        DeclarationKind::Class(_) => "Class",
+        DeclarationKind::InstanceChain(_) => "InstanceChain",
+        DeclarationKind::Operator { .. } => "Operator",
+    }
+}
+
+fn expr_kind_name(kind: &ExprKind) -> &'static str {
+    match kind {
+        ExprKind::Literal(_) => "Literal",
+        ExprKind::Infix(..) => "Infix",
+        ExprKind::Accessor(..) => "Accessor",
+        ExprKind::RecordUpdate(..) => "RecordUpdate",
+        ExprKind::Var(_) => "Var",
+        ExprKind::Operator(_) => "Operator",
+        ExprKind::DataConstructor(_) => "DataConstructor",
+        ExprKind::App(..) => "App",
+        ExprKind::Lam(..) => "Lam",
+        ExprKind::Case { .. } => "Case",
+        ExprKind::If { .. } => "If",
+        ExprKind::Typed(..) => "Typed",
+        ExprKind::TypeApp(..) => "TypeApp",
+        ExprKind::Let { .. } => "Let",
+        ExprKind::Wildcard => "Wildcard",
+        ExprKind::Hole(_) => "Hole",
+        ExprKind::RecordUpdateSuffix(_) => "RecordUpdateSuffix",
+        ExprKind::NamedPat(..) => "NamedPat",
+        ExprKind::Do(..) => "Do",
+        ExprKind::Ado(..) => "Ado",
+        ExprKind::Negate(_) => "Negate",
+        ExprKind::Section(_) => "Section",
+        ExprKind::Error => "Error",
+    }
+}
+
+fn pat_kind_name(kind: &PatKind) -> &'static str {
+    match kind {
+        PatKind::Literal(_) => "Literal",
+        PatKind::Infix(..) => "Infix",
+        PatKind::Var(_) => "Var",
+        PatKind::DataConstructorApp(..) => "DataConstructorApp",
+        PatKind::Wildcard => "Wildcard",
+        PatKind::Named(..) => "Named",
+        PatKind::Typed(..) => "Typed",
+        PatKind::Record(_) => "Record",
+        PatKind::Error => "Error",
+    }
+}
+
+fn type_kind_name(kind: &TypeKind) -> &'static str {
+    match kind {
+        TypeKind::Unknown(_) => "Unknown",
+        TypeKind::Var(_) => "Var",
+        TypeKind::Operator(_) => "Operator",
+        TypeKind::TypeLevelString(_) => "TypeLevelString",
+        TypeKind::TypeLevelInt(_) => "TypeLevelInt",
+        TypeKind::Wildcard(_) => "Wildcard",
+        TypeKind::TypeConstructor(_) => "TypeConstructor",
+        TypeKind::TypeApp(..) => "TypeApp",
+        TypeKind::FunctionType(..) => "FunctionType",
+        TypeKind::KindApp(..) => "KindApp",
+        TypeKind::ForAll { .. } => "ForAll",
+        TypeKind::Constrained { .. } => "Constrained",
+        TypeKind::Skolem(..) => "Skolem",
+        TypeKind::Row { .. } => "Row",
+        TypeKind::Kinded { .. } => "Kinded",
+        TypeKind::Infix(..) => "Infix",
+        TypeKind::Error => "Error",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::tests::parse_module_id;
+    use indoc::indoc;
+
+    #[test]
+    fn count_nodes() {
+        let db = &crate::Database::new();
+        let input = indoc!(
+            "
+            module Test where
+            f :: Int -> Int
+            f x = case g x of
+              Just y -> h y
+              _ -> 0
+            "
+        );
+        let module_id = parse_module_id(input, db);
+        let (errors, module) = crate::parser::parse_module(db, input, module_id);
+        assert_eq!(errors, vec![]);
+        let metrics = ast_metrics(&module.unwrap());
+
+        assert_eq!(metrics.declarations["TypeSignature"], 1);
+        assert_eq!(metrics.declarations["ValueDeclaration"], 1);
+        // g x, h y
+        assert_eq!(metrics.exprs["App"], 2);
+        assert_eq!(metrics.exprs["Case"], 1);
+        // g x h y
+        assert_eq!(metrics.exprs["Var"], 4);
+        // x y
+        assert_eq!(metrics.pats["Var"], 2);
+        // 2 declarations, 8 expressions, 4 patterns, 3 types
+        assert_eq!(metrics.nodes, 17);
+        // declaration > case > application > variable
+        assert_eq!(metrics.max_depth, 4);
+    }
+}
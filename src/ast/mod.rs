@@ -1,7 +1,10 @@
 pub mod declarations;
 pub mod expr;
 pub mod meta;
+pub mod metrics;
+pub mod normalize;
 pub mod types;
+pub mod visit;
 
 use crate::{renamed_module::DeclId, ModuleId};
 pub use declarations::*;
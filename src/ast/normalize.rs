@@ -0,0 +1,393 @@
+use super::*;
+use crate::source_span::SourceSpan;
+
+impl Module {
+    /// Returns a copy of the module with every source span replaced by `SourceSpan::unknown()`.
+    ///
+    /// Two modules that differ only in formatting normalize to equal values. Declaration order is
+    /// kept, because signatures and equations are grouped by adjacency.
+    pub fn normalize(&self) -> Module {
+        let mut module = self.clone();
+        module.strip_spans();
+        module
+    }
+}
+
+/// Deep replacement of source spans, used to compare ASTs regardless of their position in source.
+pub trait StripSpans {
+    fn strip_spans(&mut self);
+}
+
+impl<T: StripSpans> StripSpans for Located<T> {
+    fn strip_spans(&mut self) {
+        self.0 = SourceSpan::unknown();
+        self.1.strip_spans();
+    }
+}
+
+impl<T: StripSpans> StripSpans for Commented<T> {
+    fn strip_spans(&mut self) {
+        self.1.strip_spans();
+    }
+}
+
+impl<T: StripSpans> StripSpans for Box<T> {
+    fn strip_spans(&mut self) {
+        (**self).strip_spans();
+    }
+}
+
+impl<T: StripSpans> StripSpans for Option<T> {
+    fn strip_spans(&mut self) {
+        if let Some(x) = self {
+            x.strip_spans();
+        }
+    }
+}
+
+impl<T: StripSpans> StripSpans for Vec<T> {
+    fn strip_spans(&mut self) {
+        for x in self {
+            x.strip_spans();
+        }
+    }
+}
+
+impl<T: StripSpans> StripSpans for (Symbol, T) {
+    fn strip_spans(&mut self) {
+        self.1.strip_spans();
+    }
+}
+
+impl StripSpans for ModuleInner {
+    fn strip_spans(&mut self) {
+        self.exports.strip_spans();
+        self.imports.strip_spans();
+        self.declarations.strip_spans();
+    }
+}
+
+impl StripSpans for ImportInner {
+    fn strip_spans(&mut self) {
+        match &mut self.kind {
+            ImportDeclarationKind::Implicit => {}
+            ImportDeclarationKind::Explicit(refs) | ImportDeclarationKind::Hiding(refs) => {
+                refs.strip_spans()
+            }
+        }
+    }
+}
+
+impl StripSpans for DeclarationRefKind {
+    fn strip_spans(&mut self) {}
+}
+
+impl StripSpans for DeclarationKind {
+    fn strip_spans(&mut self) {
+        match self {
+            DeclarationKind::Data {
+                params,
+                kind,
+                constructors,
+                ..
+            } => {
+                params.strip_spans();
+                kind.strip_spans();
+                constructors.strip_spans();
+            }
+            DeclarationKind::TypeSynonym { params, body, .. } => {
+                params.strip_spans();
+                body.strip_spans();
+            }
+            DeclarationKind::KindSignature { kind, .. } => kind.strip_spans(),
+            DeclarationKind::TypeSignature(sig) => sig.r#type.strip_spans(),
+            DeclarationKind::ValueDeclaration(value) => {
+                value.params.strip_spans();
+                value.expr.strip_spans();
+                value.where_.strip_spans();
+            }
+            DeclarationKind::Destructuring { pat, expr, where_ } => {
+                pat.strip_spans();
+                expr.strip_spans();
+                where_.strip_spans();
+            }
+            DeclarationKind::ForeignValue { type_, .. } => type_.strip_spans(),
+            DeclarationKind::Class(class) => {
+                class.constraints.strip_spans();
+                class.params.strip_spans();
+                for method in &mut class.methods {
+                    method.r#type.strip_spans();
+                }
+            }
+            DeclarationKind::InstanceChain(instances) => {
+                for instance in instances {
+                    instance.constraints.strip_spans();
+                    instance.args.strip_spans();
+                    instance.body.strip_spans();
+                }
+            }
+            DeclarationKind::Role(_) | DeclarationKind::Operator { .. } => {}
+        }
+    }
+}
+
+impl StripSpans for DataConstructorDeclarationData {
+    fn strip_spans(&mut self) {
+        self.fields.strip_spans();
+    }
+}
+
+impl StripSpans for PossiblyGuardedExpr {
+    fn strip_spans(&mut self) {
+        match self {
+            PossiblyGuardedExpr::Unconditional(expr) => expr.strip_spans(),
+            PossiblyGuardedExpr::Guarded(guarded) => {
+                for guarded_expr in guarded {
+                    for guard in &mut guarded_expr.guards {
+                        match guard {
+                            Guard::Expr(expr) => expr.strip_spans(),
+                            Guard::Bind(pat, expr) => {
+                                pat.strip_spans();
+                                expr.strip_spans();
+                            }
+                        }
+                    }
+                    guarded_expr.expr.strip_spans();
+                }
+            }
+        }
+    }
+}
+
+impl<T: StripSpans> StripSpans for Literal<T> {
+    fn strip_spans(&mut self) {
+        match self {
+            Literal::Array(items) => items.strip_spans(),
+            Literal::Object(fields) => fields.strip_spans(),
+            Literal::Integer(_)
+            | Literal::Float(_)
+            | Literal::String(_)
+            | Literal::Char(_)
+            | Literal::Boolean(_) => {}
+        }
+    }
+}
+
+impl StripSpans for ExprKind {
+    fn strip_spans(&mut self) {
+        match self {
+            ExprKind::Literal(lit) => lit.strip_spans(),
+            ExprKind::Infix(first, rest) => {
+                first.strip_spans();
+                for (op, expr) in rest {
+                    op.strip_spans();
+                    expr.strip_spans();
+                }
+            }
+            ExprKind::Accessor(expr, _)
+            | ExprKind::NamedPat(_, expr)
+            | ExprKind::Negate(expr)
+            | ExprKind::Section(expr) => expr.strip_spans(),
+            ExprKind::RecordUpdate(expr, updates) => {
+                expr.strip_spans();
+                updates.strip_spans();
+            }
+            ExprKind::RecordUpdateSuffix(updates) => updates.strip_spans(),
+            ExprKind::Operator(op) => op.strip_spans(),
+            ExprKind::App(f, args) => {
+                f.strip_spans();
+                args.strip_spans();
+            }
+            ExprKind::Lam(params, body) => {
+                params.strip_spans();
+                body.strip_spans();
+            }
+            ExprKind::Case { exprs, branches } => {
+                exprs.strip_spans();
+                for branch in branches {
+                    branch.pats.strip_spans();
+                    branch.expr.strip_spans();
+                    branch.where_.strip_spans();
+                }
+            }
+            ExprKind::If { cond, then_, else_ } => {
+                cond.strip_spans();
+                then_.strip_spans();
+                else_.strip_spans();
+            }
+            ExprKind::Typed(expr, type_) | ExprKind::TypeApp(expr, type_) => {
+                expr.strip_spans();
+                type_.strip_spans();
+            }
+            ExprKind::Let { decls, body } => {
+                decls.strip_spans();
+                body.strip_spans();
+            }
+            ExprKind::Do(_, items) => items.strip_spans(),
+            ExprKind::Ado(_, items, body) => {
+                items.strip_spans();
+                body.strip_spans();
+            }
+            ExprKind::Var(_)
+            | ExprKind::DataConstructor(_)
+            | ExprKind::Wildcard
+            | ExprKind::Hole(_)
+            | ExprKind::Error => {}
+        }
+    }
+}
+
+impl StripSpans for InfixOpKind {
+    fn strip_spans(&mut self) {
+        match self {
+            InfixOpKind::Symbol(_) => {}
+            InfixOpKind::Backtick(expr) => expr.strip_spans(),
+        }
+    }
+}
+
+impl StripSpans for DoItem {
+    fn strip_spans(&mut self) {
+        match self {
+            DoItem::Let(decls) => decls.strip_spans(),
+            DoItem::Expr(expr) => expr.strip_spans(),
+            DoItem::Bind(pat, expr) => {
+                pat.strip_spans();
+                expr.strip_spans();
+            }
+        }
+    }
+}
+
+impl StripSpans for PatKind {
+    fn strip_spans(&mut self) {
+        match self {
+            PatKind::Literal(lit) => lit.strip_spans(),
+            PatKind::Infix(first, rest) => {
+                first.strip_spans();
+                rest.iter_mut().for_each(|(_, pat)| pat.strip_spans());
+            }
+            PatKind::DataConstructorApp(_, type_args, args) => {
+                type_args.strip_spans();
+                args.strip_spans();
+            }
+            PatKind::Named(_, pat) => pat.strip_spans(),
+            PatKind::Typed(pat, type_) => {
+                pat.strip_spans();
+                type_.strip_spans();
+            }
+            PatKind::Record(fields) => fields.strip_spans(),
+            PatKind::Var(_) | PatKind::Wildcard | PatKind::Error => {}
+        }
+    }
+}
+
+impl StripSpans for TypeKind {
+    fn strip_spans(&mut self) {
+        match self {
+            TypeKind::TypeApp(a, b)
+            | TypeKind::FunctionType(a, b)
+            | TypeKind::KindApp(a, b)
+            | TypeKind::Kinded { r#type: a, kind: b } => {
+                a.strip_spans();
+                b.strip_spans();
+            }
+            TypeKind::ForAll { vars, body, .. } => {
+                vars.strip_spans();
+                body.strip_spans();
+            }
+            TypeKind::Constrained { constraint, body } => {
+                constraint.strip_spans();
+                body.strip_spans();
+            }
+            TypeKind::Skolem(_, kind, _, _) => kind.strip_spans(),
+            TypeKind::Row { fields, rest } => {
+                fields.strip_spans();
+                rest.strip_spans();
+            }
+            TypeKind::Infix(first, rest) => {
+                first.strip_spans();
+                rest.iter_mut().for_each(|(_, type_)| type_.strip_spans());
+            }
+            TypeKind::Unknown(_)
+            | TypeKind::Var(_)
+            | TypeKind::Operator(_)
+            | TypeKind::TypeLevelString(_)
+            | TypeKind::TypeLevelInt(_)
+            | TypeKind::Wildcard(_)
+            | TypeKind::TypeConstructor(_)
+            | TypeKind::Error => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::utils::tests::parse_module_id;
+    use indoc::indoc;
+
+    fn parse(db: &crate::Database, input: &str) -> super::Module {
+        let module_id = parse_module_id(input, db);
+        let (errors, module) = crate::parser::parse_module(db, input, module_id);
+        assert_eq!(errors, vec![]);
+        module.unwrap()
+    }
+
+    #[test]
+    fn differently_formatted_modules_normalize_equal() {
+        let db = &crate::Database::new();
+        let a = parse(
+            db,
+            indoc!(
+                "
+                module Test (f, Maybe(..)) where
+                import Prelude (map)
+                data Maybe a = Nothing | Just a
+                f :: forall a. Maybe a -> Array a
+                f (Just x) = [x]
+                f _ = case 1 of
+                  n | n > 0 -> []
+                "
+            ),
+        );
+        let b = parse(
+            db,
+            indoc!(
+                "
+                module Test
+                  ( f
+                  , Maybe(..)
+                  ) where
+
+                import Prelude ( map )
+
+                data Maybe a
+                  = Nothing
+                  | Just a
+
+                f
+                  :: forall a
+                   . Maybe a
+                  -> Array a
+                f ( Just x ) =
+                  [ x ]
+                f _ =
+                  case 1 of
+                    n
+                      | n > 0
+                      -> [ ]
+                "
+            ),
+        );
+        assert_ne!(a, b);
+        assert_eq!(a.normalize(), b.normalize());
+    }
+
+    #[test]
+    fn different_modules_normalize_different() {
+        let db = &crate::Database::new();
+        let a = parse(db, "module Test where\nx = 1\n");
+        let b = parse(db, "module Test where\nx = 2\n");
+        assert_ne!(a.normalize(), b.normalize());
+    }
+}
@@ -4,6 +4,7 @@ use salsa::DebugWithDb;
 
 pub type Type = Located<TypeKind>;
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Eq, PartialEq, Debug, Hash, Clone, DebugWithDb)]
 pub enum TypeKind {
     /// Only appears during typechecking
@@ -56,6 +57,7 @@ pub enum TypeKind {
     Error,
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Eq, PartialEq, Debug, Hash, Clone, DebugWithDb)]
 pub enum WildcardKind {
     Hole(Symbol),
@@ -63,12 +65,14 @@ pub enum WildcardKind {
     Ignored,
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Eq, PartialEq, Debug, Hash, Clone, DebugWithDb)]
 pub struct SkolemScope(pub u64);
 
 pub type Constraint = Box<Type>;
 /*
  * for now just Type
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug)]
 pub struct Constraint {
     pub class: QualifiedName,
@@ -0,0 +1,423 @@
+//! Read-only traversal of the AST.
+//!
+//! Implement [`Visitor`] and override only the hooks you care about; the default implementation
+//! of every `visit_*` method calls the matching `walk_*` function, which recurses into the node's
+//! children. An overriding hook can call the `walk_*` function itself to keep descending.
+
+use super::*;
+use crate::source_span::SourceSpan;
+
+pub trait Visitor: Sized {
+    fn visit_module(&mut self, module: &Module) {
+        walk_module(self, module)
+    }
+
+    fn visit_declaration(&mut self, decl: &Declaration) {
+        walk_declaration(self, decl)
+    }
+
+    fn visit_expr(&mut self, expr: &Expr) {
+        walk_expr(self, expr)
+    }
+
+    fn visit_possibly_guarded_expr(&mut self, expr: &PossiblyGuardedExpr) {
+        walk_possibly_guarded_expr(self, expr)
+    }
+
+    fn visit_do_item(&mut self, item: &DoItem) {
+        walk_do_item(self, item)
+    }
+
+    fn visit_infix_op(&mut self, op: &InfixOp) {
+        walk_infix_op(self, op)
+    }
+
+    fn visit_pat(&mut self, pat: &Pat) {
+        walk_pat(self, pat)
+    }
+
+    /// Called for every variable bound by a pattern, including `x` in `x@pat`.
+    fn visit_pat_binder(&mut self, _span: SourceSpan, _name: Symbol) {}
+
+    fn visit_type(&mut self, type_: &Type) {
+        walk_type(self, type_)
+    }
+}
+
+pub fn walk_module<V: Visitor>(v: &mut V, module: &Module) {
+    for decl in &module.declarations {
+        v.visit_declaration(decl);
+    }
+}
+
+pub fn walk_declaration<V: Visitor>(v: &mut V, decl: &Declaration) {
+    match &decl.1 .1 {
+        DeclarationKind::Data {
+            params,
+            kind,
+            constructors,
+            ..
+        } => {
+            walk_type_params(v, params);
+            if let Some(kind) = kind {
+                v.visit_type(kind);
+            }
+            for constructor in constructors {
+                for field in &constructor.1 .1.fields {
+                    v.visit_type(field);
+                }
+            }
+        }
+        DeclarationKind::TypeSynonym { params, body, .. } => {
+            walk_type_params(v, params);
+            v.visit_type(body);
+        }
+        DeclarationKind::KindSignature { kind, .. } => v.visit_type(kind),
+        DeclarationKind::TypeSignature(sig) => v.visit_type(&sig.r#type),
+        DeclarationKind::ValueDeclaration(value) => {
+            for pat in &value.params {
+                v.visit_pat(pat);
+            }
+            v.visit_possibly_guarded_expr(&value.expr);
+            for decl in &value.where_ {
+                v.visit_declaration(decl);
+            }
+        }
+        DeclarationKind::Destructuring { pat, expr, where_ } => {
+            v.visit_pat(pat);
+            v.visit_possibly_guarded_expr(expr);
+            for decl in where_ {
+                v.visit_declaration(decl);
+            }
+        }
+        DeclarationKind::ForeignValue { type_, .. } => v.visit_type(type_),
+        DeclarationKind::Class(class) => {
+            for constraint in &class.constraints {
+                v.visit_type(constraint);
+            }
+            walk_type_params(v, &class.params);
+            for method in &class.methods {
+                v.visit_type(&method.r#type);
+            }
+        }
+        DeclarationKind::InstanceChain(instances) => {
+            for instance in instances {
+                for type_ in instance.constraints.iter().chain(&instance.args) {
+                    v.visit_type(type_);
+                }
+                for decl in &instance.body {
+                    v.visit_declaration(decl);
+                }
+            }
+        }
+        DeclarationKind::Role(_) | DeclarationKind::Operator { .. } => {}
+    }
+}
+
+fn walk_type_params<V: Visitor>(v: &mut V, params: &[TypeParameter]) {
+    for (_, kind) in params {
+        if let Some(kind) = kind {
+            v.visit_type(kind);
+        }
+    }
+}
+
+pub fn walk_possibly_guarded_expr<V: Visitor>(v: &mut V, expr: &PossiblyGuardedExpr) {
+    match expr {
+        PossiblyGuardedExpr::Unconditional(expr) => v.visit_expr(expr),
+        PossiblyGuardedExpr::Guarded(guarded) => {
+            for guarded_expr in guarded {
+                for guard in &guarded_expr.guards {
+                    match guard {
+                        Guard::Expr(expr) => v.visit_expr(expr),
+                        Guard::Bind(pat, expr) => {
+                            v.visit_pat(pat);
+                            v.visit_expr(expr);
+                        }
+                    }
+                }
+                v.visit_expr(&guarded_expr.expr);
+            }
+        }
+    }
+}
+
+pub fn walk_do_item<V: Visitor>(v: &mut V, item: &DoItem) {
+    match item {
+        DoItem::Let(decls) => {
+            for decl in decls {
+                v.visit_declaration(decl);
+            }
+        }
+        DoItem::Expr(expr) => v.visit_expr(expr),
+        DoItem::Bind(pat, expr) => {
+            v.visit_pat(pat);
+            v.visit_expr(expr);
+        }
+    }
+}
+
+pub fn walk_infix_op<V: Visitor>(v: &mut V, op: &InfixOp) {
+    match &op.1 {
+        InfixOpKind::Symbol(_) => {}
+        InfixOpKind::Backtick(expr) => v.visit_expr(expr),
+    }
+}
+
+pub fn walk_expr<V: Visitor>(v: &mut V, expr: &Expr) {
+    match &expr.1 {
+        ExprKind::Literal(Literal::Array(items)) => {
+            for item in items {
+                v.visit_expr(item);
+            }
+        }
+        ExprKind::Literal(Literal::Object(fields)) => {
+            for (_, value) in fields {
+                v.visit_expr(value);
+            }
+        }
+        ExprKind::Literal(
+            Literal::Integer(_)
+            | Literal::Float(_)
+            | Literal::String(_)
+            | Literal::Char(_)
+            | Literal::Boolean(_),
+        ) => {}
+        ExprKind::Infix(first, rest) => {
+            v.visit_expr(first);
+            for (op, expr) in rest {
+                v.visit_infix_op(op);
+                v.visit_expr(expr);
+            }
+        }
+        ExprKind::Accessor(expr, _)
+        | ExprKind::NamedPat(_, expr)
+        | ExprKind::Negate(expr)
+        | ExprKind::Section(expr) => v.visit_expr(expr),
+        ExprKind::RecordUpdate(expr, updates) => {
+            v.visit_expr(expr);
+            for (_, value) in updates {
+                v.visit_expr(value);
+            }
+        }
+        ExprKind::RecordUpdateSuffix(updates) => {
+            for (_, value) in updates {
+                v.visit_expr(value);
+            }
+        }
+        ExprKind::Operator(op) => v.visit_infix_op(op),
+        ExprKind::App(f, args) => {
+            v.visit_expr(f);
+            for arg in args {
+                v.visit_expr(arg);
+            }
+        }
+        ExprKind::Lam(params, body) => {
+            for pat in params {
+                v.visit_pat(pat);
+            }
+            v.visit_expr(body);
+        }
+        ExprKind::Case { exprs, branches } => {
+            for expr in exprs {
+                v.visit_expr(expr);
+            }
+            for branch in branches {
+                for pat in &branch.pats {
+                    v.visit_pat(pat);
+                }
+                v.visit_possibly_guarded_expr(&branch.expr);
+                for decl in &branch.where_ {
+                    v.visit_declaration(decl);
+                }
+            }
+        }
+        ExprKind::If { cond, then_, else_ } => {
+            v.visit_expr(cond);
+            v.visit_expr(then_);
+            v.visit_expr(else_);
+        }
+        ExprKind::Typed(expr, type_) | ExprKind::TypeApp(expr, type_) => {
+            v.visit_expr(expr);
+            v.visit_type(type_);
+        }
+        ExprKind::Let { decls, body } => {
+            for decl in decls {
+                v.visit_declaration(decl);
+            }
+            v.visit_expr(body);
+        }
+        ExprKind::Do(_, items) => {
+            for item in items {
+                v.visit_do_item(item);
+            }
+        }
+        ExprKind::Ado(_, items, body) => {
+            for item in items {
+                v.visit_do_item(item);
+            }
+            v.visit_expr(body);
+        }
+        ExprKind::Var(_)
+        | ExprKind::DataConstructor(_)
+        | ExprKind::Wildcard
+        | ExprKind::Hole(_)
+        | ExprKind::Error => {}
+    }
+}
+
+pub fn walk_pat<V: Visitor>(v: &mut V, pat: &Pat) {
+    match &pat.1 {
+        PatKind::Var(name) => v.visit_pat_binder(pat.0, *name),
+        PatKind::Named(name, inner) => {
+            v.visit_pat_binder(pat.0, *name);
+            v.visit_pat(inner);
+        }
+        PatKind::Literal(Literal::Array(items)) => {
+            for item in items {
+                v.visit_pat(item);
+            }
+        }
+        PatKind::Literal(Literal::Object(fields)) => {
+            for (_, value) in fields {
+                v.visit_pat(value);
+            }
+        }
+        PatKind::Literal(
+            Literal::Integer(_)
+            | Literal::Float(_)
+            | Literal::String(_)
+            | Literal::Char(_)
+            | Literal::Boolean(_),
+        ) => {}
+        PatKind::Infix(first, rest) => {
+            v.visit_pat(first);
+            for (_, pat) in rest {
+                v.visit_pat(pat);
+            }
+        }
+        PatKind::DataConstructorApp(_, type_args, args) => {
+            for type_arg in type_args {
+                v.visit_type(type_arg);
+            }
+            for arg in args {
+                v.visit_pat(arg);
+            }
+        }
+        PatKind::Typed(inner, type_) => {
+            v.visit_pat(inner);
+            v.visit_type(type_);
+        }
+        PatKind::Record(fields) => {
+            for (label, value) in fields {
+                match value {
+                    Some(value) => v.visit_pat(value),
+                    // Puns carry no span of their own
+                    None => v.visit_pat_binder(pat.0, *label),
+                }
+            }
+        }
+        PatKind::Wildcard | PatKind::Error => {}
+    }
+}
+
+pub fn walk_type<V: Visitor>(v: &mut V, type_: &Type) {
+    match &type_.1 {
+        TypeKind::TypeApp(a, b)
+        | TypeKind::FunctionType(a, b)
+        | TypeKind::KindApp(a, b)
+        | TypeKind::Kinded { r#type: a, kind: b } => {
+            v.visit_type(a);
+            v.visit_type(b);
+        }
+        TypeKind::ForAll { vars, body, .. } => {
+            for (_, kind) in vars {
+                if let Some(kind) = kind {
+                    v.visit_type(kind);
+                }
+            }
+            v.visit_type(body);
+        }
+        TypeKind::Constrained { constraint, body } => {
+            v.visit_type(constraint);
+            v.visit_type(body);
+        }
+        TypeKind::Skolem(_, kind, _, _) => {
+            if let Some(kind) = kind {
+                v.visit_type(kind);
+            }
+        }
+        TypeKind::Row { fields, rest } => {
+            for (_, field) in fields {
+                v.visit_type(field);
+            }
+            if let Some(rest) = rest {
+                v.visit_type(rest);
+            }
+        }
+        TypeKind::Infix(first, rest) => {
+            v.visit_type(first);
+            for (_, type_) in rest {
+                v.visit_type(type_);
+            }
+        }
+        TypeKind::Unknown(_)
+        | TypeKind::Var(_)
+        | TypeKind::Operator(_)
+        | TypeKind::TypeLevelString(_)
+        | TypeKind::TypeLevelInt(_)
+        | TypeKind::Wildcard(_)
+        | TypeKind::TypeConstructor(_)
+        | TypeKind::Error => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::tests::dummy_module;
+    use indoc::indoc;
+
+    #[derive(Default)]
+    struct VarCounter {
+        vars: usize,
+        binders: usize,
+    }
+
+    impl Visitor for VarCounter {
+        fn visit_expr(&mut self, expr: &Expr) {
+            if let ExprKind::Var(_) = expr.1 {
+                self.vars += 1;
+            }
+            walk_expr(self, expr);
+        }
+
+        fn visit_pat_binder(&mut self, _span: SourceSpan, _name: Symbol) {
+            self.binders += 1;
+        }
+    }
+
+    #[test]
+    fn count_vars() {
+        let db = &crate::Database::new();
+        let module = dummy_module(db);
+        let input = indoc!(
+            "
+            \\f -> do
+              x <- f a
+              let y = case x of
+                    Just z | z > b -> [z, x `g` c]
+                    _ -> { w: d }
+              pure (y :: T)
+            "
+        );
+        let (errors, expr) = crate::parser::parse_expr(db, input, module);
+        assert_eq!(errors, vec![]);
+        let mut counter = VarCounter::default();
+        counter.visit_expr(&expr.unwrap());
+        // f a x z b z x g c d pure y
+        assert_eq!(counter.vars, 12);
+        // f x z
+        assert_eq!(counter.binders, 3);
+    }
+}
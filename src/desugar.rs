@@ -0,0 +1,171 @@
+use crate::ast::{DoItem, Expr, ExprKind, Located, PatKind, QualifiedName};
+use crate::source_span::SourceSpan;
+use crate::symbol::Symbol;
+use crate::Diagnostic;
+use crate::ModuleId;
+
+/// Turns the statements of a `do` block into nested `bind` and `discard` calls.
+///
+/// - `x <- e` followed by `rest` becomes `bind e (\x -> rest)`
+/// - a non-final expression statement `e` becomes `discard e (\_ -> rest)`
+/// - `let decls` becomes `let decls in rest`
+/// - the final statement must be an expression and is the result of the block
+///
+/// For a qualified `M.do` block, `qualifier` is `M` and `M.bind` and `M.discard` are used instead.
+///
+/// Nested `do` blocks inside the statements are left as they are.
+pub fn desugar_do(
+    db: &dyn crate::Db,
+    span: SourceSpan,
+    qualifier: Option<ModuleId>,
+    items: Vec<DoItem>,
+) -> (Vec<Diagnostic>, Expr) {
+    let mut diagnostics = vec![];
+    let mut items = items;
+    let mut result = match items.pop() {
+        Some(DoItem::Expr(expr)) => expr,
+        Some(DoItem::Bind(pat, expr)) => {
+            diagnostics.push(Diagnostic::new(
+                SourceSpan {
+                    end: expr.0.end,
+                    ..pat.0
+                },
+                "The last statement in a do block must be an expression".into(),
+            ));
+            Located(expr.0, ExprKind::Error)
+        }
+        Some(DoItem::Let(decls)) => {
+            diagnostics.push(Diagnostic::new(
+                decls.first().map_or(span, |decl| decl.0),
+                "The last statement in a do block must be an expression".into(),
+            ));
+            Located(span, ExprKind::Error)
+        }
+        None => {
+            diagnostics.push(Diagnostic::new(span, "Empty do block".into()));
+            Located(span, ExprKind::Error)
+        }
+    };
+    while let Some(item) = items.pop() {
+        result = match item {
+            DoItem::Expr(expr) => {
+                let lam = Located(
+                    result.0,
+                    ExprKind::Lam(vec![Located(expr.0, PatKind::Wildcard)], Box::new(result)),
+                );
+                call(db, qualifier, "discard", expr, lam)
+            }
+            DoItem::Bind(pat, expr) => {
+                let lam = Located(result.0, ExprKind::Lam(vec![pat], Box::new(result)));
+                call(db, qualifier, "bind", expr, lam)
+            }
+            DoItem::Let(decls) => {
+                let start = decls.first().map_or(result.0.start, |decl| decl.0.start);
+                Located(
+                    SourceSpan { start, ..result.0 },
+                    ExprKind::Let {
+                        decls,
+                        body: Box::new(result),
+                    },
+                )
+            }
+        };
+    }
+    (diagnostics, result)
+}
+
+/// `f statement continuation`, spanning from the statement to the end of the continuation.
+fn call(
+    db: &dyn crate::Db,
+    qualifier: Option<ModuleId>,
+    f: &str,
+    statement: Expr,
+    continuation: Expr,
+) -> Expr {
+    let span = SourceSpan {
+        end: continuation.0.end,
+        ..statement.0
+    };
+    let f = Located(
+        statement.0,
+        ExprKind::Var(QualifiedName::new(db, qualifier, Symbol::new(db, f.into()))),
+    );
+    Located(
+        span,
+        ExprKind::App(Box::new(f), vec![statement, continuation]),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pretty_printer::pp;
+    use crate::utils::tests::*;
+    use indoc::indoc;
+
+    fn desugar(input: &str) -> (Vec<String>, String) {
+        let db = &crate::Database::new();
+        let module = dummy_module(db);
+        let (errors, expr) = crate::parser::parse_expr(db, input, module);
+        assert_eq!(errors, vec![]);
+        let Located(span, ExprKind::Do(qualifier, items)) = expr.unwrap() else {
+            panic!("expected do block")
+        };
+        let (diagnostics, expr) = desugar_do(db, span, qualifier, items);
+        (
+            diagnostics.into_iter().map(|d| d.message).collect(),
+            format!("{}", pp(db, &expr)),
+        )
+    }
+
+    #[test]
+    fn leading_discard() {
+        assert_eq!(
+            desugar(indoc!(
+                "
+                do
+                  logThis
+                  x <- get
+                  pure x
+                "
+            )),
+            (
+                vec![],
+                "discard logThis (\\_ -> bind get (\\x -> pure x))".into()
+            )
+        );
+    }
+
+    #[test]
+    fn final_bind() {
+        let (errors, _) = desugar(indoc!(
+            "
+            do
+              logThis
+              x <- get
+            "
+        ));
+        assert_eq!(
+            errors,
+            vec!["The last statement in a do block must be an expression"]
+        );
+    }
+
+    #[test]
+    fn qualified_do() {
+        assert_eq!(
+            desugar(indoc!(
+                "
+                M.do
+                  logThis
+                  x <- get
+                  pure x
+                "
+            )),
+            (
+                vec![],
+                "M.discard logThis (\\_ -> M.bind get (\\x -> pure x))".into()
+            )
+        );
+    }
+}
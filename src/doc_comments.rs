@@ -0,0 +1,117 @@
+use crate::ast::{CommentKind, Declaration, DeclarationKind, Module};
+use crate::symbol::Symbol;
+
+/// Returns the doc comments (`-- |`) attached to the declaration named `name`, joined with
+/// newlines. Ordinary comments are not documentation.
+///
+/// A value is usually documented on its type signature, so the first declaration with that name
+/// that has any doc comments attached wins.
+pub fn doc_comment_for(module: &Module, name: Symbol) -> Option<String> {
+    let lines = module
+        .declarations
+        .iter()
+        .filter(|decl| declaration_name(decl) == Some(name))
+        .map(|decl| {
+            decl.1
+                 .0
+                .iter()
+                .filter(|comment| comment.1 == CommentKind::Doc)
+                .map(|comment| comment.0.strip_prefix(' ').unwrap_or(&comment.0))
+                .collect::<Vec<_>>()
+        })
+        .find(|lines| !lines.is_empty())?;
+    Some(lines.join("\n"))
+}
+
+fn declaration_name(decl: &Declaration) -> Option<Symbol> {
+    match &decl.1 .1 {
+        DeclarationKind::Data { name, .. }
+        | DeclarationKind::TypeSynonym { name, .. }
+        | DeclarationKind::KindSignature { name, .. }
+        | DeclarationKind::ForeignValue { name, .. } => Some(*name),
+        DeclarationKind::Role(role) => Some(role.ident),
+        DeclarationKind::TypeSignature(sig) => Some(sig.ident),
+        DeclarationKind::ValueDeclaration(value) => Some(value.ident),
+        DeclarationKind::Class(class) => Some(class.name),
+        DeclarationKind::Operator { operator, .. } => Some(*operator),
+        DeclarationKind::InstanceChain(_) | DeclarationKind::Destructuring { .. } => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::Comment;
+    use crate::utils::tests::parse_module_id;
+    use indoc::indoc;
+
+    #[test]
+    fn test_doc_comment_for_value() {
+        let db = crate::Database::new();
+        let input = indoc!(
+            "
+            module Test where
+            -- | The answer to
+            -- | a smaller question.
+            foo :: Int
+            foo = 1
+            -- Not documentation
+            bar = 2
+            "
+        );
+        let module_id = parse_module_id(input, &db);
+        let module = crate::parser::parse_module(&db, input, module_id)
+            .1
+            .unwrap();
+
+        assert_eq!(
+            doc_comment_for(&module, Symbol::new(&db, "foo".into())),
+            Some("The answer to\na smaller question.".into())
+        );
+        assert_eq!(
+            doc_comment_for(&module, Symbol::new(&db, "bar".into())),
+            None
+        );
+    }
+
+    #[test]
+    fn test_comments_attached_by_parser() {
+        let db = crate::Database::new();
+        let input = indoc!(
+            "
+            -- | The module
+            module Test where
+            import Prelude
+            {- The first
+               value -}
+            foo = 1 -- not about bar
+            bar = do
+              -- inside bar
+              pure 2
+            -- about baz
+            baz = 3
+            "
+        );
+        let module_id = parse_module_id(input, &db);
+        let module = crate::parser::parse_module(&db, input, module_id)
+            .1
+            .unwrap();
+        assert_eq!(
+            module.1 .0,
+            vec![Comment(" The module".into(), CommentKind::Doc)]
+        );
+        let comments = module
+            .declarations
+            .iter()
+            .map(|decl| decl.1 .0.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            comments,
+            vec![
+                vec![Comment("The first\n   value".into(), CommentKind::Ordinary)],
+                vec![],
+                vec![Comment("about baz".into(), CommentKind::Ordinary)],
+            ]
+        );
+    }
+}
@@ -41,13 +41,47 @@ pub enum ErrorKind {
     InvalidClassHead,
     InvalidInstanceHead,
     InvalidFloatingPointNumber,
+    UnexpectedForallAfterConstraint,
+    InstanceNotAtTopLevel,
+    InvalidPrecedence,
+    LowercaseModuleNameComponent,
     NonUsvChar,
+    PunInRecordUpdate,
+    LiteralFieldInRecordUpdate,
+    CaseArityMismatch { scrutinees: usize, patterns: usize },
+    UnknownFundepVariable(String),
     Unknown(String),
     Error(LexerError),
 }
 
 impl Display for ErrorKind {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        write!(f, "{:?}", self)
+        match self {
+            Self::LowercaseModuleNameComponent => {
+                write!(f, "module name components must start uppercase")
+            }
+            Self::PunInRecordUpdate => write!(
+                f,
+                "record update fields need a value, e.g. `x = x`; puns are only allowed in record literals"
+            ),
+            Self::LiteralFieldInRecordUpdate => write!(
+                f,
+                "record update fields are set with `=`; `:` is only allowed in record literals"
+            ),
+            Self::CaseArityMismatch {
+                scrutinees,
+                patterns,
+            } => write!(
+                f,
+                "case branch has {} patterns, but there are {} scrutinees",
+                patterns, scrutinees
+            ),
+            Self::UnknownFundepVariable(var) => write!(
+                f,
+                "type variable `{}` in a functional dependency is not a class parameter",
+                var
+            ),
+            _ => write!(f, "{:?}", self),
+        }
     }
 }
@@ -0,0 +1,791 @@
+use crate::ast::{
+    Associativity, CaseBranch, Declaration, DeclarationKind, DoItem, Expr, ExprKind, Guard,
+    InfixOp, InfixOpKind, Literal, Located, Module, OperatorTarget, Pat, PatKind,
+    PossiblyGuardedExpr, QualifiedName, Type, TypeKind,
+};
+use crate::source_span::SourceSpan;
+use crate::Diagnostic;
+use fxhash::FxHashMap;
+
+#[derive(Eq, PartialEq, Debug, Hash, Clone)]
+pub struct Fixity {
+    pub associativity: Associativity,
+    pub precedence: u8,
+}
+
+impl Fixity {
+    pub fn new(associativity: Associativity, precedence: u8) -> Self {
+        Self {
+            associativity,
+            precedence,
+        }
+    }
+}
+
+pub type Fixities = FxHashMap<QualifiedName, Fixity>;
+
+/// Fixities of the operators in scope, keyed by operator name.
+///
+/// Operators missing from the environment, as well as backtick operators, are treated as
+/// `infixl 9`.
+#[derive(PartialEq, Eq, Clone, Debug, Default)]
+pub struct FixityEnv {
+    /// Value and data constructor operators
+    pub values: Fixities,
+    /// Type operators
+    pub types: Fixities,
+    /// Data constructors aliased by operators, so that operator patterns can be resolved to
+    /// applications of the constructor itself
+    pub constructors: FxHashMap<QualifiedName, QualifiedName>,
+}
+
+impl FixityEnv {
+    /// Collects the fixity declarations of a module, keyed by the unqualified operator.
+    pub fn from_module(db: &dyn crate::Db, module: &Module) -> Self {
+        let mut env = Self::default();
+        for decl in &module.declarations {
+            if let DeclarationKind::Operator {
+                associativity,
+                precedence,
+                name,
+                operator,
+            } = &decl.1 .1
+            {
+                if let OperatorTarget::DataConstructor(constructor) = name {
+                    env.constructors
+                        .insert(QualifiedName::new_unqualified(db, *operator), *constructor);
+                }
+                let fixities = match name {
+                    OperatorTarget::Type(_) => &mut env.types,
+                    OperatorTarget::Value(_) | OperatorTarget::DataConstructor(_) => {
+                        &mut env.values
+                    }
+                };
+                fixities.insert(
+                    QualifiedName::new_unqualified(db, *operator),
+                    Fixity::new(associativity.clone(), *precedence),
+                );
+            }
+        }
+        env
+    }
+}
+
+const DEFAULT_FIXITY: Fixity = Fixity {
+    associativity: Associativity::Left,
+    precedence: 9,
+};
+
+/// Rewrites every `Infix` chain in the expression into nested applications of the operators.
+///
+/// `a + b` becomes `App(Operator(+), [a, b])`, and ``a `f` b`` becomes `App(f, [a, b])`. Operators
+/// in patterns become `DataConstructorApp`s of the aliased constructor, or of the operator itself
+/// if its target is unknown. Operator sections are desugared into lambdas first.
+pub fn resolve_fixity(db: &dyn crate::Db, env: &FixityEnv, expr: Expr) -> (Vec<Diagnostic>, Expr) {
+    let mut resolver = Resolver {
+        db,
+        env,
+        diagnostics: vec![],
+    };
+    let mut expr = expr;
+    resolver.expr(&mut expr);
+    (resolver.diagnostics, expr)
+}
+
+/// Rewrites every `Infix` chain in the type into nested applications of the type operators, so
+/// `a <> b` becomes `TypeApp(TypeApp(Operator(<>), a), b)`.
+pub fn resolve_type_fixity(
+    db: &dyn crate::Db,
+    env: &FixityEnv,
+    type_: Type,
+) -> (Vec<Diagnostic>, Type) {
+    let mut resolver = Resolver {
+        db,
+        env,
+        diagnostics: vec![],
+    };
+    let mut type_ = type_;
+    resolver.type_(&mut type_);
+    (resolver.diagnostics, type_)
+}
+
+struct Resolver<'a> {
+    db: &'a dyn crate::Db,
+    env: &'a FixityEnv,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl<'a> Resolver<'a> {
+    fn op_fixity(fixities: &'a Fixities, op: &InfixOp) -> &'a Fixity {
+        match &op.1 {
+            InfixOpKind::Symbol(name) => fixities.get(name).unwrap_or(&DEFAULT_FIXITY),
+            InfixOpKind::Backtick(_) => &DEFAULT_FIXITY,
+        }
+    }
+
+    fn op_name(&self, op: &InfixOp) -> String {
+        match &op.1 {
+            InfixOpKind::Symbol(name) => name.name(self.db).text(self.db).to_string(),
+            InfixOpKind::Backtick(_) => "backtick operator".into(),
+        }
+    }
+
+    fn decls(&mut self, decls: &mut [Declaration]) {
+        for decl in decls {
+            self.decl(decl);
+        }
+    }
+
+    fn decl(&mut self, decl: &mut Declaration) {
+        match &mut decl.1 .1 {
+            DeclarationKind::ValueDeclaration(value_decl) => {
+                self.pats(&mut value_decl.params);
+                self.possibly_guarded(&mut value_decl.expr);
+                self.decls(&mut value_decl.where_);
+            }
+            DeclarationKind::Destructuring { pat, expr, where_ } => {
+                self.pat(pat);
+                self.possibly_guarded(expr);
+                self.decls(where_);
+            }
+            DeclarationKind::InstanceChain(instances) => {
+                for instance in instances {
+                    self.decls(&mut instance.body);
+                }
+            }
+            DeclarationKind::Data { .. }
+            | DeclarationKind::TypeSynonym { .. }
+            | DeclarationKind::KindSignature { .. }
+            | DeclarationKind::Role(_)
+            | DeclarationKind::TypeSignature(_)
+            | DeclarationKind::ForeignValue { .. }
+            | DeclarationKind::Class(_)
+            | DeclarationKind::Operator { .. } => {}
+        }
+    }
+
+    fn possibly_guarded(&mut self, expr: &mut PossiblyGuardedExpr) {
+        match expr {
+            PossiblyGuardedExpr::Unconditional(expr) => self.expr(expr),
+            PossiblyGuardedExpr::Guarded(guarded) => {
+                for guarded_expr in guarded {
+                    for guard in &mut guarded_expr.guards {
+                        match guard {
+                            Guard::Expr(expr) => self.expr(expr),
+                            Guard::Bind(pat, expr) => {
+                                self.pat(pat);
+                                self.expr(expr);
+                            }
+                        }
+                    }
+                    self.expr(&mut guarded_expr.expr);
+                }
+            }
+        }
+    }
+
+    fn do_items(&mut self, items: &mut [DoItem]) {
+        for item in items {
+            match item {
+                DoItem::Let(decls) => self.decls(decls),
+                DoItem::Expr(expr) => self.expr(expr),
+                DoItem::Bind(pat, expr) => {
+                    self.pat(pat);
+                    self.expr(expr);
+                }
+            }
+        }
+    }
+
+    fn exprs(&mut self, exprs: &mut [Expr]) {
+        for expr in exprs {
+            self.expr(expr);
+        }
+    }
+
+    fn expr(&mut self, expr: &mut Expr) {
+        match &mut expr.1 {
+            ExprKind::Literal(lit) => match lit {
+                Literal::Array(items) => self.exprs(items),
+                Literal::Object(fields) => {
+                    for (_, value) in fields {
+                        self.expr(value);
+                    }
+                }
+                Literal::Integer(_)
+                | Literal::Float(_)
+                | Literal::String(_)
+                | Literal::Char(_)
+                | Literal::Boolean(_) => {}
+            },
+            ExprKind::Infix(..) => {
+                let ExprKind::Infix(first, rest) = std::mem::replace(&mut expr.1, ExprKind::Error)
+                else {
+                    unreachable!()
+                };
+                let mut first = *first;
+                self.expr(&mut first);
+                let rest = rest
+                    .into_iter()
+                    .map(|(mut op, mut x)| {
+                        self.infix_op(&mut op);
+                        self.expr(&mut x);
+                        (op, x)
+                    })
+                    .collect();
+                *expr = self.resolve_chain(&self.env.values, first, rest, |op, lhs, rhs| {
+                    let span = SourceSpan {
+                        end: rhs.0.end,
+                        ..lhs.0
+                    };
+                    let f = match op {
+                        Located(_, InfixOpKind::Backtick(f)) => *f,
+                        op => Located(op.0, ExprKind::Operator(op)),
+                    };
+                    Located(span, ExprKind::App(Box::new(f), vec![lhs, rhs]))
+                });
+            }
+            ExprKind::Accessor(expr, _) => self.expr(expr),
+            ExprKind::RecordUpdate(expr, updates) => {
+                self.expr(expr);
+                for (_, value) in updates {
+                    self.expr(value);
+                }
+            }
+            ExprKind::RecordUpdateSuffix(updates) => {
+                for (_, value) in updates {
+                    self.expr(value);
+                }
+            }
+            ExprKind::Operator(op) => self.infix_op(op),
+            ExprKind::App(f, args) => {
+                self.expr(f);
+                self.exprs(args);
+            }
+            ExprKind::Lam(params, body) => {
+                self.pats(params);
+                self.expr(body);
+            }
+            ExprKind::Case { exprs, branches } => {
+                self.exprs(exprs);
+                for CaseBranch { pats, expr, where_ } in branches {
+                    self.pats(pats);
+                    self.possibly_guarded(expr);
+                    self.decls(where_);
+                }
+            }
+            ExprKind::If { cond, then_, else_ } => {
+                self.expr(cond);
+                self.expr(then_);
+                self.expr(else_);
+            }
+            ExprKind::Typed(expr, type_) | ExprKind::TypeApp(expr, type_) => {
+                self.expr(expr);
+                self.type_(type_);
+            }
+            ExprKind::NamedPat(_, expr) | ExprKind::Negate(expr) => self.expr(expr),
+            ExprKind::Section(_) => {
+                let ExprKind::Section(inner) = std::mem::replace(&mut expr.1, ExprKind::Error)
+                else {
+                    unreachable!()
+                };
+                expr.1 = crate::parser::desugar_section(self.db, *inner);
+                self.expr(expr);
+            }
+            ExprKind::Let { decls, body } => {
+                self.decls(decls);
+                self.expr(body);
+            }
+            ExprKind::Do(_, items) => self.do_items(items),
+            ExprKind::Ado(_, items, body) => {
+                self.do_items(items);
+                self.expr(body);
+            }
+            ExprKind::Var(_)
+            | ExprKind::DataConstructor(_)
+            | ExprKind::Wildcard
+            | ExprKind::Hole(_)
+            | ExprKind::Error => {}
+        }
+    }
+
+    fn infix_op(&mut self, op: &mut InfixOp) {
+        match &mut op.1 {
+            InfixOpKind::Symbol(_) => {}
+            InfixOpKind::Backtick(expr) => self.expr(expr),
+        }
+    }
+
+    fn pats(&mut self, pats: &mut [Pat]) {
+        for pat in pats {
+            self.pat(pat);
+        }
+    }
+
+    fn pat(&mut self, pat: &mut Pat) {
+        match &mut pat.1 {
+            PatKind::Literal(lit) => match lit {
+                Literal::Array(items) => self.pats(items),
+                Literal::Object(fields) => {
+                    for (_, value) in fields {
+                        self.pat(value);
+                    }
+                }
+                Literal::Integer(_)
+                | Literal::Float(_)
+                | Literal::String(_)
+                | Literal::Char(_)
+                | Literal::Boolean(_) => {}
+            },
+            PatKind::Infix(..) => {
+                let PatKind::Infix(first, rest) = std::mem::replace(&mut pat.1, PatKind::Error)
+                else {
+                    unreachable!()
+                };
+                // Pattern operators carry no span of their own, so point at the whole pattern
+                let span = pat.0;
+                let constructors = &self.env.constructors;
+                let mut first = *first;
+                self.pat(&mut first);
+                let rest = rest
+                    .into_iter()
+                    .map(|(op, mut x)| {
+                        self.pat(&mut x);
+                        (Located(span, InfixOpKind::Symbol(op)), x)
+                    })
+                    .collect();
+                *pat = self.resolve_chain(&self.env.values, first, rest, |op, lhs, rhs| {
+                    let InfixOpKind::Symbol(name) = op.1 else {
+                        unreachable!("backtick operators are not allowed in patterns")
+                    };
+                    let name = *constructors.get(&name).unwrap_or(&name);
+                    let span = SourceSpan {
+                        end: rhs.0.end,
+                        ..lhs.0
+                    };
+                    Located(
+                        span,
+                        PatKind::DataConstructorApp(name, vec![], vec![lhs, rhs]),
+                    )
+                });
+            }
+            PatKind::DataConstructorApp(_, type_args, args) => {
+                for type_arg in type_args {
+                    self.type_(type_arg);
+                }
+                self.pats(args);
+            }
+            PatKind::Named(_, pat) => self.pat(pat),
+            PatKind::Typed(pat, type_) => {
+                self.pat(pat);
+                self.type_(type_);
+            }
+            PatKind::Record(fields) => {
+                for (_, value) in fields {
+                    if let Some(value) = value {
+                        self.pat(value);
+                    }
+                }
+            }
+            PatKind::Var(_) | PatKind::Wildcard | PatKind::Error => {}
+        }
+    }
+
+    fn type_(&mut self, type_: &mut Type) {
+        match &mut type_.1 {
+            TypeKind::Infix(..) => {
+                let TypeKind::Infix(first, rest) = std::mem::replace(&mut type_.1, TypeKind::Error)
+                else {
+                    unreachable!()
+                };
+                // Type operators carry no span of their own, so point at the whole type
+                let span = type_.0;
+                let mut first = *first;
+                self.type_(&mut first);
+                let rest = rest
+                    .into_iter()
+                    .map(|(op, mut x)| {
+                        self.type_(&mut x);
+                        (Located(span, InfixOpKind::Symbol(op)), x)
+                    })
+                    .collect();
+                *type_ = self.resolve_chain(&self.env.types, first, rest, |op, lhs, rhs| {
+                    let InfixOpKind::Symbol(name) = op.1 else {
+                        unreachable!("backtick operators are not allowed in types")
+                    };
+                    let span = SourceSpan {
+                        end: rhs.0.end,
+                        ..lhs.0
+                    };
+                    let op = Located(op.0, TypeKind::Operator(name));
+                    let partial = Located(
+                        SourceSpan {
+                            end: op.0.end,
+                            ..lhs.0
+                        },
+                        TypeKind::TypeApp(Box::new(op), Box::new(lhs)),
+                    );
+                    Located(span, TypeKind::TypeApp(Box::new(partial), Box::new(rhs)))
+                });
+            }
+            TypeKind::TypeApp(a, b)
+            | TypeKind::FunctionType(a, b)
+            | TypeKind::KindApp(a, b)
+            | TypeKind::Kinded { r#type: a, kind: b } => {
+                self.type_(a);
+                self.type_(b);
+            }
+            TypeKind::ForAll { vars, body, .. } => {
+                for (_, kind) in vars {
+                    if let Some(kind) = kind {
+                        self.type_(kind);
+                    }
+                }
+                self.type_(body);
+            }
+            TypeKind::Constrained { constraint, body } => {
+                self.type_(constraint);
+                self.type_(body);
+            }
+            TypeKind::Skolem(_, kind, _, _) => {
+                if let Some(kind) = kind {
+                    self.type_(kind);
+                }
+            }
+            TypeKind::Row { fields, rest } => {
+                for (_, field) in fields {
+                    self.type_(field);
+                }
+                if let Some(rest) = rest {
+                    self.type_(rest);
+                }
+            }
+            TypeKind::Unknown(_)
+            | TypeKind::Var(_)
+            | TypeKind::Operator(_)
+            | TypeKind::TypeLevelString(_)
+            | TypeKind::TypeLevelInt(_)
+            | TypeKind::Wildcard(_)
+            | TypeKind::TypeConstructor(_)
+            | TypeKind::Error => {}
+        }
+    }
+
+    /// Resolves a flat operator chain using the operator-precedence (shunting-yard) algorithm.
+    fn resolve_chain<T>(
+        &mut self,
+        fixities: &'a Fixities,
+        first: Located<T>,
+        rest: Vec<(InfixOp, Located<T>)>,
+        mut apply: impl FnMut(InfixOp, Located<T>, Located<T>) -> Located<T>,
+    ) -> Located<T> {
+        let mut operands = vec![first];
+        let mut operators: Vec<InfixOp> = vec![];
+
+        for (op, operand) in rest {
+            while let Some(top) = operators.last() {
+                let top_fixity = Self::op_fixity(fixities, top);
+                let fixity = Self::op_fixity(fixities, &op);
+                let reduce = if top_fixity.precedence != fixity.precedence {
+                    top_fixity.precedence > fixity.precedence
+                } else {
+                    match (&top_fixity.associativity, &fixity.associativity) {
+                        (Associativity::Left, Associativity::Left) => true,
+                        (Associativity::Right, Associativity::Right) => false,
+                        _ => {
+                            self.diagnostics.push(Diagnostic::new(
+                                op.0,
+                                format!(
+                                    "Cannot mix operators {} and {} of the same precedence without parentheses",
+                                    self.op_name(top),
+                                    self.op_name(&op)
+                                ),
+                            ));
+                            true
+                        }
+                    }
+                };
+                if !reduce {
+                    break;
+                }
+                Self::reduce(&mut operands, &mut operators, &mut apply);
+            }
+            operators.push(op);
+            operands.push(operand);
+        }
+
+        while !operators.is_empty() {
+            Self::reduce(&mut operands, &mut operators, &mut apply);
+        }
+        operands.pop().unwrap()
+    }
+
+    fn reduce<T>(
+        operands: &mut Vec<Located<T>>,
+        operators: &mut Vec<InfixOp>,
+        apply: &mut impl FnMut(InfixOp, Located<T>, Located<T>) -> Located<T>,
+    ) {
+        let op = operators.pop().unwrap();
+        let rhs = operands.pop().unwrap();
+        let lhs = operands.pop().unwrap();
+        operands.push(apply(op, lhs, rhs));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::symbol::Symbol;
+    use crate::utils::tests::*;
+    use crate::Db;
+    use indoc::indoc;
+
+    fn env(db: &dyn Db, ops: &[(&str, Associativity, u8)]) -> FixityEnv {
+        FixityEnv {
+            values: ops
+                .iter()
+                .map(|(op, associativity, precedence)| {
+                    (
+                        QualifiedName::new_unqualified(db, Symbol::new(db, op.to_string())),
+                        Fixity::new(associativity.clone(), *precedence),
+                    )
+                })
+                .collect(),
+            types: Default::default(),
+            constructors: Default::default(),
+        }
+    }
+
+    /// Shows the resolved tree with every operator application parenthesized.
+    fn show(db: &dyn Db, expr: &Expr) -> String {
+        match &expr.1 {
+            ExprKind::App(f, args) => {
+                let f = match &f.1 {
+                    ExprKind::Operator(Located(_, InfixOpKind::Symbol(op))) => {
+                        op.name(db).text(db).to_string()
+                    }
+                    _ => format!("`{}`", show(db, f)),
+                };
+                format!("({} {} {})", show(db, &args[0]), f, show(db, &args[1]))
+            }
+            ExprKind::Var(name) => name.name(db).text(db).to_string(),
+            _ => panic!("unexpected expression {:?}", expr),
+        }
+    }
+
+    fn resolve(ops: &[(&str, Associativity, u8)], input: &str) -> (Vec<String>, String) {
+        let db = &crate::Database::new();
+        let module = dummy_module(db);
+        let (errors, expr) = crate::parser::parse_expr(db, input, module);
+        assert_eq!(errors, vec![]);
+        let (diagnostics, expr) = resolve_fixity(db, &env(db, ops), expr.unwrap());
+        (
+            diagnostics.into_iter().map(|d| d.message).collect(),
+            show(db, &expr),
+        )
+    }
+
+    #[test]
+    fn left_associative() {
+        assert_eq!(
+            resolve(&[("-", Associativity::Left, 6)], "a - b - c"),
+            (vec![], "((a - b) - c)".into())
+        );
+    }
+
+    #[test]
+    fn right_associative() {
+        assert_eq!(
+            resolve(&[("$", Associativity::Right, 0)], "f $ g $ x"),
+            (vec![], "(f $ (g $ x))".into())
+        );
+    }
+
+    #[test]
+    fn mixed_precedence() {
+        assert_eq!(
+            resolve(
+                &[
+                    ("+", Associativity::Left, 6),
+                    ("*", Associativity::Left, 7),
+                    ("<>", Associativity::Right, 5),
+                ],
+                "a + b * c <> d <> e"
+            ),
+            (vec![], "((a + (b * c)) <> (d <> e))".into())
+        );
+    }
+
+    #[test]
+    fn backtick_operator() {
+        assert_eq!(
+            resolve(&[("+", Associativity::Left, 6)], "a + b `div` c"),
+            (vec![], "(a + (b `div` c))".into())
+        );
+    }
+
+    #[test]
+    fn non_associative_conflict() {
+        let (errors, _) = resolve(&[("==", Associativity::None, 4)], "a == b == c");
+        assert_eq!(
+            errors,
+            vec!["Cannot mix operators == and == of the same precedence without parentheses"]
+        );
+    }
+
+    #[test]
+    fn mixed_associativity_conflict() {
+        let (errors, _) = resolve(
+            &[
+                ("+", Associativity::Left, 6),
+                ("++", Associativity::Right, 6),
+            ],
+            "a + b ++ c",
+        );
+        assert_eq!(
+            errors,
+            vec!["Cannot mix operators + and ++ of the same precedence without parentheses"]
+        );
+    }
+
+    #[test]
+    fn infix_pattern() {
+        let db = &crate::Database::new();
+        let module = dummy_module(db);
+        let (errors, expr) = crate::parser::parse_expr(db, "case xs of x : y : ys -> ys", module);
+        assert_eq!(errors, vec![]);
+        let env = env(db, &[(":", Associativity::Right, 6)]);
+        let (diagnostics, expr) = resolve_fixity(db, &env, expr.unwrap());
+        assert_eq!(diagnostics, vec![]);
+        let ExprKind::Case { branches, .. } = expr.1 else {
+            panic!("expected case")
+        };
+        let PatKind::DataConstructorApp(op, _, args) = &branches[0].pats[0].1 else {
+            panic!("expected constructor application")
+        };
+        assert_eq!(op.name(db).text(db), ":");
+        assert!(matches!(args[0].1, PatKind::Var(_)));
+        assert!(matches!(args[1].1, PatKind::DataConstructorApp(_, _, _)));
+    }
+
+    /// Shows a resolved pattern with every constructor application parenthesized.
+    fn show_pat(db: &dyn Db, pat: &Pat) -> String {
+        match &pat.1 {
+            PatKind::DataConstructorApp(name, _, args) if args.is_empty() => {
+                name.name(db).text(db).to_string()
+            }
+            PatKind::DataConstructorApp(name, _, args) => format!(
+                "({}{})",
+                name.name(db).text(db),
+                args.iter()
+                    .map(|arg| format!(" {}", show_pat(db, arg)))
+                    .collect::<String>()
+            ),
+            PatKind::Var(name) => name.text(db).to_string(),
+            _ => panic!("unexpected pattern {:?}", pat),
+        }
+    }
+
+    #[test]
+    fn constructor_operator_pattern() {
+        let db = &crate::Database::new();
+        let input = indoc!(
+            "
+            module Test where
+            data List a = Nil | Cons a (List a)
+            infixr 6 Cons as :
+            f (Just x : rest) = rest
+            "
+        );
+        let module_id = parse_module_id(input, db);
+        let (errors, module) = crate::parser::parse_module(db, input, module_id);
+        assert_eq!(errors, vec![]);
+        let module = module.unwrap();
+        let env = FixityEnv::from_module(db, &module);
+        let DeclarationKind::ValueDeclaration(value) = &module.declarations[2].1 .1 else {
+            panic!("expected value declaration")
+        };
+        let mut pat = value.params[0].clone();
+        let mut resolver = Resolver {
+            db,
+            env: &env,
+            diagnostics: vec![],
+        };
+        resolver.pat(&mut pat);
+        assert_eq!(resolver.diagnostics, vec![]);
+        assert_eq!(show_pat(db, &pat), "(Cons (Just x) rest)");
+    }
+
+    #[test]
+    fn operator_data_constructor_pattern() {
+        let db = &crate::Database::new();
+        let input = indoc!(
+            "
+            module Test where
+            data NonEmpty a = (:|) a (Array a)
+            infixr 5 (:|) as :|
+            f (x :| y :| rest) = rest
+            "
+        );
+        let module_id = parse_module_id(input, db);
+        let (errors, module) = crate::parser::parse_module(db, input, module_id);
+        assert_eq!(errors, vec![]);
+        let module = module.unwrap();
+        let env = FixityEnv::from_module(db, &module);
+        let DeclarationKind::ValueDeclaration(value) = &module.declarations[2].1 .1 else {
+            panic!("expected value declaration")
+        };
+        let mut pat = value.params[0].clone();
+        let mut resolver = Resolver {
+            db,
+            env: &env,
+            diagnostics: vec![],
+        };
+        resolver.pat(&mut pat);
+        assert_eq!(resolver.diagnostics, vec![]);
+        assert_eq!(show_pat(db, &pat), "(:| x (:| y rest))");
+    }
+
+    fn show_type(db: &dyn Db, type_: &Type) -> String {
+        match &type_.1 {
+            TypeKind::TypeApp(f, rhs) => match &f.1 {
+                TypeKind::TypeApp(op, lhs) => match &op.1 {
+                    TypeKind::Operator(op) => format!(
+                        "({} {} {})",
+                        show_type(db, lhs),
+                        op.name(db).text(db),
+                        show_type(db, rhs)
+                    ),
+                    _ => panic!("unexpected type {:?}", type_),
+                },
+                _ => panic!("unexpected type {:?}", type_),
+            },
+            TypeKind::Var(name) => name.text(db).to_string(),
+            _ => panic!("unexpected type {:?}", type_),
+        }
+    }
+
+    #[test]
+    fn type_operator_declared_in_module() {
+        let db = &crate::Database::new();
+        let input = indoc!(
+            "
+            module Test where
+            infixr 6 type Append as <>
+            infixl 6 append as <>
+            type Three a b c = a <> b <> c
+            "
+        );
+        let module_id = parse_module_id(input, db);
+        let (errors, module) = crate::parser::parse_module(db, input, module_id);
+        assert_eq!(errors, vec![]);
+        let module = module.unwrap();
+        let env = FixityEnv::from_module(db, &module);
+        let DeclarationKind::TypeSynonym { body, .. } = &module.declarations[2].1 .1 else {
+            panic!("expected type synonym")
+        };
+        let (diagnostics, type_) = resolve_type_fixity(db, &env, body.clone());
+        assert_eq!(diagnostics, vec![]);
+        assert_eq!(show_type(db, &type_), "(a <> (b <> c))");
+    }
+}
@@ -0,0 +1,295 @@
+use crate::ast::visit::{walk_declaration, walk_expr, Visitor};
+use crate::ast::{
+    Declaration, DeclarationKind, DoItem, Expr, ExprKind, Guard, Pat, PossiblyGuardedExpr, Type,
+};
+use crate::source_span::SourceSpan;
+use crate::symbol::Symbol;
+use std::collections::HashSet;
+
+/// Returns the variables used in the expression but not bound inside it.
+///
+/// Only unqualified variables are considered; a qualified `Var` always refers to another module.
+/// Operators and data constructors are not variables.
+pub fn free_vars(db: &dyn crate::Db, expr: &Expr) -> HashSet<Symbol> {
+    let mut collector = FreeVars {
+        db,
+        scopes: vec![],
+        free: HashSet::new(),
+    };
+    collector.visit_expr(expr);
+    collector.free
+}
+
+/// Returns the variables bound by the pattern with the spans of their binders, in source order.
+///
+/// For `x@pat` the span is the one of the whole named pattern, and for a pun the one of the whole
+/// record pattern.
+pub fn pattern_vars(pat: &Pat) -> Vec<(Symbol, SourceSpan)> {
+    let mut collector = PatternVars(vec![]);
+    collector.visit_pat(pat);
+    collector.0
+}
+
+struct PatternVars(Vec<(Symbol, SourceSpan)>);
+
+impl Visitor for PatternVars {
+    fn visit_pat_binder(&mut self, span: SourceSpan, name: Symbol) {
+        self.0.push((name, span));
+    }
+
+    // Nothing in a type annotation binds a value
+    fn visit_type(&mut self, _type: &Type) {}
+}
+
+struct FreeVars<'a> {
+    db: &'a dyn crate::Db,
+    scopes: Vec<HashSet<Symbol>>,
+    free: HashSet<Symbol>,
+}
+
+impl FreeVars<'_> {
+    fn is_bound(&self, var: Symbol) -> bool {
+        self.scopes.iter().any(|scope| scope.contains(&var))
+    }
+
+    fn in_scope(&mut self, f: impl FnOnce(&mut Self)) {
+        self.scopes.push(HashSet::new());
+        f(self);
+        self.scopes.pop();
+    }
+
+    /// Binds the names declared by `let`/`where` declarations in the current scope, so that they
+    /// can refer to each other, then visits the declarations.
+    fn let_decls(&mut self, decls: &[Declaration]) {
+        for decl in decls {
+            match &decl.1 .1 {
+                DeclarationKind::ValueDeclaration(value) => {
+                    self.visit_pat_binder(decl.0, value.ident);
+                }
+                DeclarationKind::Destructuring { pat, .. } => self.visit_pat(pat),
+                _ => {}
+            }
+        }
+        for decl in decls {
+            self.visit_declaration(decl);
+        }
+    }
+
+    fn do_items(&mut self, items: &[DoItem], body: Option<&Expr>) {
+        let mut scopes = 0;
+        // In `ado`, binds are only visible in the final `in` expression
+        let mut ado_binders = vec![];
+        for item in items {
+            match item {
+                DoItem::Let(decls) => {
+                    self.scopes.push(HashSet::new());
+                    scopes += 1;
+                    self.let_decls(decls);
+                }
+                DoItem::Expr(expr) => self.visit_expr(expr),
+                DoItem::Bind(pat, expr) => {
+                    self.visit_expr(expr);
+                    if body.is_some() {
+                        ado_binders.push(pat);
+                    } else {
+                        self.scopes.push(HashSet::new());
+                        scopes += 1;
+                        self.visit_pat(pat);
+                    }
+                }
+            }
+        }
+        if let Some(body) = body {
+            self.in_scope(|this| {
+                for pat in ado_binders {
+                    this.visit_pat(pat);
+                }
+                this.visit_expr(body);
+            });
+        }
+        for _ in 0..scopes {
+            self.scopes.pop();
+        }
+    }
+}
+
+impl Visitor for FreeVars<'_> {
+    fn visit_declaration(&mut self, decl: &Declaration) {
+        match &decl.1 .1 {
+            // Parameters and `where` bindings are only in scope in the declaration's own body
+            DeclarationKind::ValueDeclaration(value) => self.in_scope(|this| {
+                value.params.iter().for_each(|pat| this.visit_pat(pat));
+                this.let_decls(&value.where_);
+                this.visit_possibly_guarded_expr(&value.expr);
+            }),
+            // The pattern is bound by the enclosing `let`
+            DeclarationKind::Destructuring { expr, where_, .. } => self.in_scope(|this| {
+                this.let_decls(where_);
+                this.visit_possibly_guarded_expr(expr);
+            }),
+            _ => walk_declaration(self, decl),
+        }
+    }
+
+    fn visit_expr(&mut self, expr: &Expr) {
+        match &expr.1 {
+            ExprKind::Var(name) => {
+                let db = self.db;
+                if name.module(db).is_none() && !self.is_bound(name.name(db)) {
+                    self.free.insert(name.name(db));
+                }
+            }
+            ExprKind::Lam(params, body) => self.in_scope(|this| {
+                params.iter().for_each(|pat| this.visit_pat(pat));
+                this.visit_expr(body);
+            }),
+            ExprKind::Case { exprs, branches } => {
+                exprs.iter().for_each(|expr| self.visit_expr(expr));
+                for branch in branches {
+                    self.in_scope(|this| {
+                        branch.pats.iter().for_each(|pat| this.visit_pat(pat));
+                        this.let_decls(&branch.where_);
+                        this.visit_possibly_guarded_expr(&branch.expr);
+                    });
+                }
+            }
+            ExprKind::Let { decls, body } => self.in_scope(|this| {
+                this.let_decls(decls);
+                this.visit_expr(body);
+            }),
+            ExprKind::Do(_, items) => self.do_items(items, None),
+            ExprKind::Ado(_, items, body) => self.do_items(items, Some(body)),
+            _ => walk_expr(self, expr),
+        }
+    }
+
+    fn visit_possibly_guarded_expr(&mut self, expr: &PossiblyGuardedExpr) {
+        match expr {
+            PossiblyGuardedExpr::Unconditional(expr) => self.visit_expr(expr),
+            PossiblyGuardedExpr::Guarded(guarded) => {
+                for guarded_expr in guarded {
+                    // Pattern guards bind variables for the following guards and the body
+                    self.in_scope(|this| {
+                        for guard in &guarded_expr.guards {
+                            match guard {
+                                Guard::Expr(expr) => this.visit_expr(expr),
+                                Guard::Bind(pat, expr) => {
+                                    this.visit_expr(expr);
+                                    this.visit_pat(pat);
+                                }
+                            }
+                        }
+                        this.visit_expr(&guarded_expr.expr);
+                    });
+                }
+            }
+        }
+    }
+
+    fn visit_pat_binder(&mut self, _span: SourceSpan, name: Symbol) {
+        self.scopes
+            .last_mut()
+            .expect("pattern outside of a scope")
+            .insert(name);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::tests::dummy_module;
+    use indoc::indoc;
+
+    fn free(input: &str) -> Vec<String> {
+        let db = &crate::Database::new();
+        let module = dummy_module(db);
+        let (errors, expr) = crate::parser::parse_expr(db, input, module);
+        assert_eq!(errors, vec![]);
+        let mut vars = free_vars(db, &expr.unwrap())
+            .into_iter()
+            .map(|var| var.text(db).clone())
+            .collect::<Vec<_>>();
+        vars.sort();
+        vars
+    }
+
+    #[test]
+    fn lambda_shadows_outer_name() {
+        assert_eq!(free(r"f (\f -> f x) f"), vec!["f", "x"]);
+        assert_eq!(free(r"\f -> f x"), vec!["x"]);
+    }
+
+    #[test]
+    fn let_binds_name_in_body() {
+        assert_eq!(free("let y = z in y"), vec!["z"]);
+        assert_eq!(
+            free(indoc!(
+                r"
+                let
+                  go n = go (n - step)
+                  Tuple a b = pair
+                in go a b
+                "
+            )),
+            vec!["pair", "step"]
+        );
+    }
+
+    #[test]
+    fn case_and_do_binders() {
+        assert_eq!(
+            free(indoc!(
+                "
+                do
+                  x <- get
+                  case x of
+                    Just y | Just z <- lookup y -> put z w
+                    _ -> pure x
+                "
+            )),
+            vec!["get", "lookup", "pure", "put", "w"]
+        );
+    }
+
+    #[test]
+    fn qualified_vars_are_not_free() {
+        assert_eq!(free("Data.Array.length xs"), vec!["xs"]);
+    }
+
+    fn bound(input: &str) -> Vec<(String, &str)> {
+        let db = &crate::Database::new();
+        let module = dummy_module(db);
+        let (errors, pat) = crate::parser::parse_pattern(db, input, module);
+        assert_eq!(errors, vec![]);
+        pattern_vars(&pat.unwrap())
+            .into_iter()
+            .map(|(var, span)| (var.text(db).clone(), &input[span.start..span.end]))
+            .collect()
+    }
+
+    #[test]
+    fn pattern_vars_of_nested_constructors() {
+        assert_eq!(
+            bound("Tuple (Just x) all@(Cons y (ys :: List Int))"),
+            vec![
+                ("x".into(), "x"),
+                ("all".into(), "all@(Cons y (ys :: List Int))"),
+                ("y".into(), "y"),
+                ("ys".into(), "ys"),
+            ]
+        );
+    }
+
+    #[test]
+    fn pattern_vars_of_records_and_arrays() {
+        assert_eq!(
+            bound("{ a, b: [c, _], d: Just e } : rest"),
+            vec![
+                ("a".into(), "{ a, b: [c, _], d: Just e }"),
+                ("c".into(), "c"),
+                ("e".into(), "e"),
+                ("rest".into(), "rest"),
+            ]
+        );
+    }
+}
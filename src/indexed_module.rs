@@ -1,14 +1,18 @@
 use crate::ast::AbsoluteName;
+use crate::ast::Associativity;
 use crate::ast::CaseBranch;
 use crate::ast::DataConstructorDeclaration;
 use crate::ast::DataDeclType;
 use crate::ast::Declaration;
 use crate::ast::DeclarationKind;
 use crate::ast::Fundep;
+use crate::ast::KindSignatureFor;
 use crate::ast::Located;
+use crate::ast::OperatorTarget;
 use crate::renamed_module::DeclId;
 use crate::renamed_module::Namespace;
 use crate::source_span::SourceSpanOps;
+use crate::symbol::Symbol;
 use salsa::DebugWithDb;
 use std::iter::Peekable;
 
@@ -63,12 +67,22 @@ pub struct ValueDecl {
     pub equations: Vec<CaseBranch>,
 }
 
+/// A fixity declaration, `infixl 6 add as +`.
+#[derive(PartialEq, Eq, Clone, Debug, DebugWithDb)]
+pub struct OperatorDecl {
+    pub associativity: Associativity,
+    pub precedence: u8,
+    pub target: OperatorTarget,
+}
+
 struct ModuleIndexer<'a> {
     db: &'a dyn Db,
     // Note: Using `FxHashMap` mostly because we want deterministic order for snapshots.
     types: FxHashMap<AbsoluteName, TypeDecl>,
     values: FxHashMap<AbsoluteName, ValueDecl>,
     classes: FxHashMap<AbsoluteName, TypeClassDecl>,
+    kind_signatures: FxHashMap<AbsoluteName, Type>,
+    operators: FxHashMap<DeclId, OperatorDecl>,
     module_id: ModuleId,
     decls_ref_loc: FxHashMap<DeclId, usize>,
 }
@@ -155,7 +169,38 @@ impl<'a> ModuleIndexer<'a> {
                     iter.next();
                 }
 
-                KindSignature { .. } => todo!(),
+                KindSignature {
+                    for_type,
+                    name,
+                    kind,
+                } => {
+                    let abs_name = AbsoluteName::new(db, self.module_id, *name);
+                    let namespace = match for_type {
+                        KindSignatureFor::Data
+                        | KindSignatureFor::Newtype
+                        | KindSignatureFor::TypeSynonym => Namespace::Type,
+                        KindSignatureFor::Class => Namespace::Class,
+                    };
+                    let decl_id = abs_name.to_decl_id(db, namespace);
+
+                    match self.kind_signatures.entry(abs_name) {
+                        Entry::Occupied(_) => {
+                            Diagnostics::push(
+                                db,
+                                Diagnostic::new(
+                                    src_decl.span(),
+                                    format!("Duplicate kind signature {}", name.text(db)),
+                                ),
+                            );
+                        }
+                        Entry::Vacant(e) => {
+                            let mut kind = kind.clone();
+                            kind.to_relative_span(decl_id, ref_loc);
+                            e.insert(kind);
+                        }
+                    }
+                    iter.next();
+                }
 
                 Role(_) => todo!(),
 
@@ -263,7 +308,41 @@ impl<'a> ModuleIndexer<'a> {
                     iter.next();
                 }
                 InstanceChain(_) => todo!(),
-                Operator { .. } => todo!(),
+                Operator {
+                    associativity,
+                    precedence,
+                    name: target,
+                    operator,
+                } => {
+                    let namespace = match target {
+                        OperatorTarget::Type(_) => Namespace::Type,
+                        OperatorTarget::Value(_) | OperatorTarget::DataConstructor(_) => {
+                            Namespace::Value
+                        }
+                    };
+                    let decl_id = DeclId::new(db, namespace, self.module_id, *operator);
+                    self.decls_ref_loc.insert(decl_id, ref_loc);
+
+                    match self.operators.entry(decl_id) {
+                        Entry::Occupied(_) => {
+                            Diagnostics::push(
+                                db,
+                                Diagnostic::new(
+                                    src_decl.span(),
+                                    format!("Duplicate fixity declaration {}", operator.text(db)),
+                                ),
+                            );
+                        }
+                        Entry::Vacant(e) => {
+                            e.insert(OperatorDecl {
+                                associativity: associativity.clone(),
+                                precedence: *precedence,
+                                target: target.clone(),
+                            });
+                        }
+                    }
+                    iter.next();
+                }
             }
         }
     }
@@ -354,6 +433,9 @@ pub struct IndexedModule {
     pub types: FxHashMap<AbsoluteName, TypeDecl>,
     pub values: FxHashMap<AbsoluteName, ValueDecl>,
     pub classes: FxHashMap<AbsoluteName, TypeClassDecl>,
+    pub kind_signatures: FxHashMap<AbsoluteName, Type>,
+    /// Fixity declarations, in the namespace of the operator they declare
+    pub operators: FxHashMap<DeclId, OperatorDecl>,
     pub decls_ref_loc: FxHashMap<DeclId, usize>,
     pub filename: String,
 }
@@ -368,6 +450,8 @@ pub fn indexed_module(db: &dyn Db, module_id: ModuleId) -> IndexedModule {
         types: Default::default(),
         values: Default::default(),
         classes: Default::default(),
+        kind_signatures: Default::default(),
+        operators: Default::default(),
         module_id,
         decls_ref_loc: Default::default(),
     };
@@ -377,11 +461,27 @@ pub fn indexed_module(db: &dyn Db, module_id: ModuleId) -> IndexedModule {
         types: indexer.types,
         values: indexer.values,
         classes: indexer.classes,
+        kind_signatures: indexer.kind_signatures,
+        operators: indexer.operators,
         decls_ref_loc: indexer.decls_ref_loc,
         filename: raw_filename,
     }
 }
 
+/// Returns the kind of a type declared in the module, either from a standalone kind signature
+/// or from the declaration itself (e.g. `foreign import data Map :: Type -> Type -> Type`).
+pub fn kind_of(db: &dyn Db, module_id: ModuleId, type_name: Symbol) -> Option<Type> {
+    let module = indexed_module(db, module_id);
+    let abs_name = AbsoluteName::new(db, module_id, type_name);
+    if let Some(kind) = module.kind_signatures.get(&abs_name) {
+        return Some(kind.clone());
+    }
+    match module.types.get(&abs_name)? {
+        TypeDecl::Data(decl) => decl.kind.clone(),
+        TypeDecl::Type(_) | TypeDecl::TypeClass(_) => None,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -465,6 +565,19 @@ mod tests {
         )));
     }
 
+    #[test]
+    fn operators() {
+        assert_snapshot!(index_module(indoc!(
+            "
+        module Test where
+        type Fn a b = a
+        add x y = x
+        infixl 6 add as +~
+        infixr 4 type Fn as +~
+        "
+        )));
+    }
+
     #[test]
     fn value_decl_with_signature() {
         assert_snapshot!(index_module(indoc!(
@@ -541,4 +654,73 @@ mod tests {
         "
         )));
     }
+
+    #[test]
+    fn kind_of_foreign_data() {
+        let input = indoc!(
+            "
+        module Test where
+        foreign import data Map :: Type -> Type -> Type
+        data Maybe a = Nothing | Just a
+        "
+        );
+        let db = &crate::Database::test_single_file_db(input);
+        let module_id = parse_module_id(input, db);
+        let kind = kind_of(db, module_id, Symbol::new(db, "Map".into()));
+        assert_eq!(
+            kind.map(|kind| format!("{}", crate::pretty_printer::pp(db, kind))),
+            Some("Type -> Type -> Type".into())
+        );
+        assert_eq!(
+            kind_of(db, module_id, Symbol::new(db, "Maybe".into())),
+            None
+        );
+    }
+
+    #[test]
+    fn standalone_kind_signature() {
+        let input = indoc!(
+            "
+        module Test where
+        type Id :: Type -> Type
+        type Id a = a
+        "
+        );
+        let db = &crate::Database::test_single_file_db(input);
+        let module_id = parse_module_id(input, db);
+        let kind = kind_of(db, module_id, Symbol::new(db, "Id".into()));
+        assert_eq!(
+            kind.map(|kind| format!("{}", crate::pretty_printer::pp(db, kind))),
+            Some("Type -> Type".into())
+        );
+    }
+
+    #[test]
+    fn data_kind_signature() {
+        let input = indoc!(
+            "
+        module Test where
+        data Proxy :: forall k. k -> Type
+        data Proxy a = Proxy
+        newtype Wrap :: Type -> Type
+        newtype Wrap a = Wrap a
+        "
+        );
+        let db = &crate::Database::test_single_file_db(input);
+        let module_id = parse_module_id(input, db);
+        assert_eq!(
+            indexed_module::accumulated::<Diagnostics>(db, module_id),
+            vec![]
+        );
+        let kind = kind_of(db, module_id, Symbol::new(db, "Proxy".into()));
+        assert_eq!(
+            kind.map(|kind| format!("{}", crate::pretty_printer::pp(db, kind))),
+            Some("forall k. k -> Type".into())
+        );
+        let kind = kind_of(db, module_id, Symbol::new(db, "Wrap".into()));
+        assert_eq!(
+            kind.map(|kind| format!("{}", crate::pretty_printer::pp(db, kind))),
+            Some("Type -> Type".into())
+        );
+    }
 }
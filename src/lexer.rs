@@ -1,3 +1,4 @@
+use crate::ast::{Comment, CommentKind};
 use crate::errors::{Error, ErrorKind, LexerError, Loc};
 use crate::string::{PSChar, PSString};
 use log::trace;
@@ -7,19 +8,86 @@ use unicode_general_category::{get_general_category, GeneralCategory::*};
 
 pub use crate::token::{Token, TokenInfo};
 
+mod incremental;
+pub use incremental::{relex, LineEdit};
+
 pub type Spanned<Tok, Loc, Error> = Result<(Loc, Tok, Loc), Error>;
 
 pub fn lex(input: &str) -> impl Iterator<Item = Spanned<Token, usize, Error>> + '_ {
-    make_lexer(input).map(|r| r.map(|t| (t.start, t.token, t.end)))
+    make_lexer(input, LexOptions::default(), None).map(|r| r.map(|t| (t.start, t.token, t.end)))
+}
+
+/// Like `lex`, but with non-default options. Non-fatal problems are pushed to `warnings`.
+pub fn lex_with_options<'a>(
+    input: &'a str,
+    options: LexOptions,
+    warnings: &'a mut Vec<Error>,
+) -> impl Iterator<Item = Spanned<Token, usize, Error>> + 'a {
+    make_lexer(input, options, Some(warnings)).map(|r| r.map(|t| (t.start, t.token, t.end)))
+}
+
+/// Like `lex_with_options`, but also pushes every comment, which is left out of the token stream,
+/// to `comments`. Comments are stored without their `--` or `{-`/`-}` delimiters.
+pub fn lex_with_comments<'a>(
+    input: &'a str,
+    options: LexOptions,
+    warnings: &'a mut Vec<Error>,
+    comments: &'a mut Vec<(usize, Comment, usize)>,
+) -> impl Iterator<Item = Spanned<Token, usize, Error>> + 'a {
+    let mut lexer = make_lexer(input, options, Some(warnings));
+    lexer.comments = Some(comments);
+    lexer.map(|r| r.map(|t| (t.start, t.token, t.end)))
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LexOptions {
+    /// Accept tabs in indentation. Layout blocks which mix tabs and spaces produce a warning.
+    pub allow_tabs: bool,
+    /// Lex the Unicode spellings of keywords and reserved operators (`∀ ∷ → ⇒ ⇐ ←`) as ordinary
+    /// operators, as PureScript versions before they were reserved did.
+    pub ascii_only_syntax: bool,
+}
+
+/// Returns the raw token stream, without `LayoutStart`/`LayoutSep`/`LayoutEnd` tokens.
+/// Useful for debugging the layout algorithm.
+pub fn lex_without_layout(input: &str) -> impl Iterator<Item = Spanned<Token, usize, Error>> + '_ {
+    let mut lexer = make_lexer(input, LexOptions::default(), None);
+    lexer.layout = false;
+    lexer.map(|r| r.map(|t| (t.start, t.token, t.end)))
 }
 
-fn make_lexer(input: &str) -> impl Iterator<Item = LexResult> + '_ {
-    let mut chars = input.char_indices().peekable();
+fn make_lexer<'a>(
+    input: &'a str,
+    options: LexOptions,
+    warnings: Option<&'a mut Vec<Error>>,
+) -> Lexer<'a> {
+    make_lexer_at(input, 0, options, warnings)
+}
+
+/// Creates a lexer without layout which continues after `token`, lexed earlier from the same
+/// input. The token must not span lines.
+fn resume_lexer_after<'a>(input: &'a str, token: &TokenInfo) -> Lexer<'a> {
+    let mut lexer = make_lexer_at(input, token.trailing_space_end, LexOptions::default(), None);
+    lexer.layout = false;
+    lexer.line = token.line;
+    lexer.line_start = token.start - token.column;
+    lexer.indent_level = token.indent_level;
+    lexer
+}
+
+fn make_lexer_at<'a>(
+    input: &'a str,
+    offset: usize,
+    options: LexOptions,
+    warnings: Option<&'a mut Vec<Error>>,
+) -> Lexer<'a> {
+    let mut chars = input[offset..].char_indices().peekable();
     let first = chars.next();
     Lexer {
         input,
         chars,
-        pos: first.map(|x| x.0).unwrap_or(0),
+        chars_offset: offset,
+        pos: first.map(|x| x.0 + offset).unwrap_or(input.len()),
         current: first.map(|x| x.1),
         token_start: 0,
         whitespace_start: 0,
@@ -29,12 +97,18 @@ fn make_lexer(input: &str) -> impl Iterator<Item = LexResult> + '_ {
         line: 0,
         line_start: 0,
         layout_stack: Default::default(),
+        layout: true,
+        options,
+        warnings,
+        comments: None,
     }
 }
 
 struct Lexer<'a> {
     input: &'a str,
     chars: Peekable<CharIndices<'a>>,
+    /// Position of the start of `chars` in `input`
+    chars_offset: usize,
     pos: usize,
     current: Option<char>,
     whitespace_start: usize,
@@ -46,6 +120,12 @@ struct Lexer<'a> {
     line_start: usize,
 
     indent_level: usize,
+
+    /// Whether to run the layout algorithm
+    layout: bool,
+    options: LexOptions,
+    warnings: Option<&'a mut Vec<Error>>,
+    comments: Option<&'a mut Vec<(usize, Comment, usize)>>,
 }
 
 #[derive(Debug)]
@@ -57,6 +137,21 @@ struct LayoutEntry {
 
     // For `of` blocks:
     after_patterns: bool,
+
+    // Characters used for indenting lines in this block (only tracked when tabs are allowed)
+    indentation: Indentation,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+struct Indentation {
+    tabs: bool,
+    spaces: bool,
+}
+
+impl Indentation {
+    fn is_mixed(&self) -> bool {
+        self.tabs && self.spaces
+    }
 }
 
 pub type LexResult = Result<TokenInfo, Error>;
@@ -90,20 +185,16 @@ impl<'a> Iterator for Lexer<'a> {
             match c {
                 // Single-line comment
                 '-' if self.can_peek2() && self.peek2() == '-' => {
+                    let start = self.pos;
                     while !self.eof() && self.peek() != '\n' {
                         self.next_char();
                     }
+                    self.push_comment(start, self.pos);
                 }
-                // Multi-line comment
+                // Multi-line comment, possibly nested
                 '{' if self.can_peek2() && self.peek2() == '-' => {
-                    #[allow(clippy::nonminimal_bool)]
-                    while !self.eof()
-                        && !(self.peek() == '-' && self.can_peek2() && self.peek2() == '}')
-                    {
-                        self.next_char();
-                    }
-                    if !self.eof() {
-                        self.next_char();
+                    if let Err(err) = self.skip_block_comment() {
+                        return Some(Err(err));
                     }
                 }
                 c if !c.is_whitespace() => {
@@ -122,8 +213,13 @@ impl<'a> Iterator for Lexer<'a> {
             }
             self.next_char();
         }
+        let mut line_indentation = None;
         if let Some(line_start) = line_start {
             self.indent_level = self.pos - line_start;
+            match self.check_indentation(line_start) {
+                Ok(indentation) => line_indentation = Some(indentation),
+                Err(err) => return Some(Err(err)),
+            }
         }
 
         let result = self.lex_token();
@@ -137,6 +233,10 @@ impl<'a> Iterator for Lexer<'a> {
 
         trace!("lexed token {:?}", next_token.token);
 
+        if !self.layout {
+            return Some(Ok(next_token));
+        }
+
         // Starting a new paren block
         if let Some(prev_token) = &prev_token {
             match &prev_token.token {
@@ -154,6 +254,7 @@ impl<'a> Iterator for Lexer<'a> {
                         indent_level: next_token.column,
                         token: prev_token.token.clone(),
                         after_patterns: false,
+                        indentation: Default::default(),
                     });
                 }
                 Token::Pipe
@@ -164,6 +265,7 @@ impl<'a> Iterator for Lexer<'a> {
                         indent_level: next_token.column,
                         token: prev_token.token.clone(),
                         after_patterns: false,
+                        indentation: Default::default(),
                     });
                 }
                 _ => {}
@@ -307,6 +409,7 @@ impl<'a> Iterator for Lexer<'a> {
                             indent_level: next_token.column,
                             token: Token::Backtick,
                             after_patterns: false,
+                            indentation: Default::default(),
                         });
                         pushed_backtick = true;
                         continue;
@@ -342,8 +445,15 @@ impl<'a> Iterator for Lexer<'a> {
             break;
         }
 
-        // Starting a new layout block
-        if let Some(prev_token) = &prev_token {
+        // Starting a new layout block, unless it's delimited by explicit braces. Only a brace on
+        // the same line as the keyword can open an explicit block; on the next line, or when
+        // followed by e.g. `->`, it starts a record pattern.
+        if let Some(prev_token) = prev_token.as_ref().filter(|prev_token| {
+            next_token.token != Token::LeftBrace
+                || next_token.line != prev_token.line
+                || (starts_layout_block(&prev_token.token)
+                    && self.starts_record_pattern(&next_token))
+        }) {
             match &prev_token.token {
                 // Where doesn't need additional indentation
                 Token::Where => {
@@ -352,24 +462,37 @@ impl<'a> Iterator for Lexer<'a> {
                         indent_level: next_token.column,
                         token: prev_token.token.clone(),
                         after_patterns: false,
+                        indentation: Default::default(),
                     });
                     let token = self.make_token_info(Token::LayoutStart);
                     self.enqueue(token);
                 }
-                Token::Do | Token::Let | Token::Of | Token::Ado
+                Token::Do
+                | Token::Let
+                | Token::Of
+                | Token::Ado
+                | Token::QualifiedDo(_)
+                | Token::QualifiedAdo(_)
                     if next_token.column >= prev_token.indent_level =>
                 {
+                    let token = match &prev_token.token {
+                        // Qualified blocks are laid out like unqualified ones
+                        Token::QualifiedDo(_) => Token::Do,
+                        Token::QualifiedAdo(_) => Token::Ado,
+                        token => token.clone(),
+                    };
                     self.layout_push(LayoutEntry {
                         line: next_token.line,
                         indent_level: next_token.column,
-                        token: prev_token.token.clone(),
+                        token,
                         after_patterns: false,
+                        indentation: Default::default(),
                     });
                     let token = self.make_token_info(Token::LayoutStart);
                     self.enqueue(token);
 
                     // Annoying edge case: empty let/ado blocks
-                    if let (Token::Let | Token::Ado, Token::In) =
+                    if let (Token::Let | Token::Ado | Token::QualifiedAdo(_), Token::In) =
                         (&prev_token.token, &next_token.token)
                     {
                         trace!("ending empty let/ado");
@@ -381,6 +504,9 @@ impl<'a> Iterator for Lexer<'a> {
                 _ => {}
             }
         }
+        if let Some(indentation) = line_indentation {
+            self.record_indentation(indentation, &next_token);
+        }
         // We may have queued some token(s) above, if so queue the current one and return
         // the one from queue
         if let Some(queued_token) = self.queue.pop_front() {
@@ -391,7 +517,44 @@ impl<'a> Iterator for Lexer<'a> {
     }
 }
 
+fn starts_layout_block(token: &Token) -> bool {
+    matches!(
+        token,
+        Token::Where
+            | Token::Do
+            | Token::Let
+            | Token::Of
+            | Token::Ado
+            | Token::QualifiedDo(_)
+            | Token::QualifiedAdo(_)
+    )
+}
+
 impl<'a> Lexer<'a> {
+    /// Whether the braces starting at `brace` are a record pattern rather than an explicit-brace
+    /// block, i.e. the matching `}` is followed by `=`, `->`, `<-` or `::`.
+    fn starts_record_pattern(&self, brace: &TokenInfo) -> bool {
+        let mut tokens = resume_lexer_after(self.input, brace);
+        let mut depth = 0;
+        while let Some(Ok(token)) = tokens.next() {
+            match token.token {
+                Token::LeftBrace => depth += 1,
+                Token::RightBrace if depth > 0 => depth -= 1,
+                Token::RightBrace => {
+                    return matches!(
+                        tokens.next(),
+                        Some(Ok(TokenInfo {
+                            token: Token::Equal | Token::Arrow | Token::Bind | Token::TypeOf,
+                            ..
+                        }))
+                    )
+                }
+                _ => {}
+            }
+        }
+        false
+    }
+
     fn layout_push(&mut self, entry: LayoutEntry) {
         trace!("layout_push({:?})", entry);
         self.layout_stack.push(entry);
@@ -403,6 +566,109 @@ impl<'a> Lexer<'a> {
         entry
     }
 
+    /// Checks the leading whitespace of the line starting at `line_start`.
+    /// Tabs are rejected unless allowed by the options.
+    fn check_indentation(&self, line_start: usize) -> Result<Indentation, Error> {
+        let mut indentation = Indentation::default();
+        for (offset, c) in self.input[line_start..self.pos].char_indices() {
+            match c {
+                ' ' => indentation.spaces = true,
+                '\t' if self.options.allow_tabs => indentation.tabs = true,
+                '\t' => {
+                    let pos = line_start + offset;
+                    return Err(Error::new(
+                        pos,
+                        pos + 1,
+                        ErrorKind::Error(LexerError(
+                            "Tab characters are not allowed in indentation".to_string(),
+                        )),
+                    ));
+                }
+                _ => break,
+            }
+        }
+        Ok(indentation)
+    }
+
+    /// Adds the indentation of a line to the innermost layout block, warning when the block
+    /// starts mixing tabs and spaces.
+    fn record_indentation(&mut self, indentation: Indentation, token: &TokenInfo) {
+        if !self.options.allow_tabs {
+            return;
+        }
+        let Some(entry) = self
+            .layout_stack
+            .iter_mut()
+            .rev()
+            .find(|entry| is_indented(&entry.token))
+        else {
+            return;
+        };
+        let was_mixed = entry.indentation.is_mixed();
+        entry.indentation.tabs |= indentation.tabs;
+        entry.indentation.spaces |= indentation.spaces;
+        if !was_mixed && entry.indentation.is_mixed() {
+            if let Some(warnings) = &mut self.warnings {
+                warnings.push(Error::new(
+                    token.start,
+                    token.end,
+                    ErrorKind::Error(LexerError(
+                        "Layout block mixes tabs and spaces in indentation".to_string(),
+                    )),
+                ));
+            }
+        }
+    }
+
+    /// Skips a block comment starting at the current `{-`, including any nested ones. Stops at the
+    /// closing `}`, like line comments stop at the newline.
+    fn skip_block_comment(&mut self) -> Result<(), Error> {
+        let start = self.pos;
+        self.next_char();
+        let mut depth = 1;
+        loop {
+            self.next_char();
+            if self.eof() {
+                return Err(Error::new(
+                    start,
+                    self.pos,
+                    ErrorKind::Error(LexerError("Unterminated block comment".to_string())),
+                ));
+            }
+            match self.peek() {
+                '{' if self.can_peek2() && self.peek2() == '-' => {
+                    self.next_char();
+                    depth += 1;
+                }
+                '-' if self.can_peek2() && self.peek2() == '}' => {
+                    self.next_char();
+                    depth -= 1;
+                    if depth == 0 {
+                        self.push_comment(start, self.pos + 1);
+                        return Ok(());
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Records the line or block comment spanning `start..end`, if comments are collected.
+    fn push_comment(&mut self, start: usize, end: usize) {
+        if let Some(comments) = &mut self.comments {
+            let text = if self.input[start..].starts_with("{-") {
+                &self.input[start + 2..end - 2]
+            } else {
+                &self.input[start + 2..end]
+            };
+            let comment = match text.trim_start().strip_prefix('|') {
+                Some(doc) => Comment(doc.to_string(), CommentKind::Doc),
+                None => Comment(text.trim().to_string(), CommentKind::Ordinary),
+            };
+            comments.push((start, comment, end));
+        }
+    }
+
     fn loc_error(&self, error: String) -> Error {
         Error {
             loc: Loc {
@@ -510,7 +776,8 @@ impl<'a> Lexer<'a> {
                 ))
             }
             c if is_digit(c) => {
-                let mut value = digit_value(c);
+                // `None` if the value doesn't fit
+                let mut value = Some(digit_value(c));
                 let is_float = loop {
                     if self.eof() {
                         break false;
@@ -525,15 +792,34 @@ impl<'a> Lexer<'a> {
                         break false;
                     }
                     if is_digit(self.peek()) {
-                        value = value * 10 + digit_value(self.peek());
+                        let digit = digit_value(self.peek());
+                        value = value
+                            .and_then(|v| v.checked_mul(10))
+                            .and_then(|v| v.checked_add(digit));
                     }
                     self.next_char();
                 };
                 if is_float {
                     self.next_char();
+                    if self.eof() || !is_digit(self.peek()) {
+                        return Err(
+                            self.loc_error("Expected digits after the decimal point".to_string())
+                        );
+                    }
                     while !self.eof() && is_integer_literal_char(self.peek()) {
                         self.next_char();
                     }
+                }
+                let has_exponent = self.lex_exponent()?;
+                if !self.eof() && is_ident_start(self.peek()) {
+                    while !self.eof() && is_ident_char(self.peek()) {
+                        self.next_char();
+                    }
+                    return Err(
+                        self.loc_error("Unexpected character after numeric literal".to_string())
+                    );
+                }
+                if is_float || has_exponent {
                     self.make_token(Token::FloatLiteral(
                         self.input[self.token_start..self.pos]
                             .chars()
@@ -541,18 +827,41 @@ impl<'a> Lexer<'a> {
                             .collect(),
                     ))
                 } else {
-                    self.make_token(Token::IntegerLiteral(value as u64))
+                    match value {
+                        Some(value) => self.make_token(Token::IntegerLiteral(value)),
+                        None => Err(self.loc_error("Integer literal is too large".to_string())),
+                    }
                 }
             }
             '"' => {
+                let (line, line_start) = (self.line, self.line_start);
                 let s = self.parse_string_literal()?;
-                self.make_token(Token::StringLiteral(s))
+                // Raw strings and string gaps may span lines, but the token itself belongs to
+                // the line it started on.
+                let (next_line, next_line_start) = (self.line, self.line_start);
+                (self.line, self.line_start) = (line, line_start);
+                let token = self.make_token(Token::StringLiteral(s));
+                (self.line, self.line_start) = (next_line, next_line_start);
+                token
             }
             '\'' => {
+                if !self.eof() && self.peek() == '\'' {
+                    self.next_char();
+                    return Err(self.loc_error("Empty character literal".to_string()));
+                }
                 let c = self.parse_possibly_escaped_char()?;
-                if self.peek() != '\'' {
-                    return Err(self
-                        .loc_error(format!("Invalid end of character literal: {}", self.peek())));
+                if self.eof() || self.peek() != '\'' {
+                    // Find the closing quote to report the whole literal
+                    while !self.eof() && !matches!(self.peek(), '\'' | '\n') {
+                        self.next_char();
+                    }
+                    if self.eof() || self.peek() != '\'' {
+                        return Err(self.loc_error("Unterminated character literal".to_string()));
+                    }
+                    self.next_char();
+                    return Err(self.loc_error(
+                        "Character literal must contain exactly one character".to_string(),
+                    ));
                 }
                 self.next_char();
                 self.make_token(Token::CharLiteral(c))
@@ -566,16 +875,54 @@ impl<'a> Lexer<'a> {
             '`' => self.make_token(Token::Backtick),
             ',' => self.make_token(Token::Comma),
             ';' => self.make_token(Token::Semicolon),
+            '?' if !self.eof() && is_ident_start(self.peek()) => {
+                let qualifier = self.peek().is_uppercase();
+                while !self.eof() && is_ident_char(self.peek()) {
+                    self.next_char();
+                }
+                // `?x.y` is an accessor on a hole, but `?M.x` looks like a qualified name
+                if qualifier && !self.eof() && self.peek() == '.' {
+                    while !self.eof() && (is_ident_char(self.peek()) || self.peek() == '.') {
+                        self.next_char();
+                    }
+                    return Err(self.loc_error("Hole names can't be qualified".to_string()));
+                }
+                let name = self.input[self.token_start + 1..self.pos].into();
+                self.make_token(Token::Hole(name))
+            }
             c if is_operator_char(c) => {
                 while !self.eof() && is_operator_char(self.peek()) {
                     self.next_char();
                 }
-                self.make_token(operator_to_token(&self.input[self.token_start..self.pos]))
+                let operator = &self.input[self.token_start..self.pos];
+                if operator == "?" {
+                    return Err(self.loc_error("Expected a hole name after `?`".to_string()));
+                }
+                // Unlike in named patterns (`x@p`), the `@` of a type application is separated
+                // from the function but not from its argument: `read @Int`
+                if operator == "@"
+                    && self.follows_whitespace()
+                    && !self.eof()
+                    && !self.peek().is_whitespace()
+                {
+                    return self.make_token(Token::TypeApp);
+                }
+                if self.options.ascii_only_syntax && !operator.is_ascii() {
+                    return self.make_token(Token::Operator(operator.into()));
+                }
+                self.make_token(operator_to_token(operator))
             }
 
             _ => Err(self.loc_error(format!("Unknown character: {}", c))),
         }
     }
+    /// Whether the current token is at the start of input or preceded by whitespace.
+    fn follows_whitespace(&self) -> bool {
+        self.input[..self.token_start]
+            .chars()
+            .next_back()
+            .is_none_or(char::is_whitespace)
+    }
     fn eof(&self) -> bool {
         self.current.is_none()
     }
@@ -590,10 +937,34 @@ impl<'a> Lexer<'a> {
     }
     fn next_char(&mut self) {
         let item = self.chars.next();
-        self.pos = item.map(|x| x.0).unwrap_or(self.input.len());
+        self.pos = item
+            .map(|x| x.0 + self.chars_offset)
+            .unwrap_or(self.input.len());
         self.current = item.map(|x| x.1);
     }
 
+    /// Lexes the exponent part of a number literal (`e10`, `E-5`), if present.
+    fn lex_exponent(&mut self) -> Result<bool, Error> {
+        if self.eof()
+            || !matches!(self.peek(), 'e' | 'E')
+            || !self.can_peek2()
+            || !(is_digit(self.peek2()) || matches!(self.peek2(), '+' | '-'))
+        {
+            return Ok(false);
+        }
+        self.next_char();
+        if matches!(self.peek(), '+' | '-') {
+            self.next_char();
+        }
+        if self.eof() || !is_digit(self.peek()) {
+            return Err(self.loc_error("Expected digits in the exponent".to_string()));
+        }
+        while !self.eof() && is_integer_literal_char(self.peek()) {
+            self.next_char();
+        }
+        Ok(true)
+    }
+
     fn make_token(&mut self, token: Token) -> LexResult {
         Ok(self.make_token_info(token))
     }
@@ -667,8 +1038,14 @@ impl<'a> Lexer<'a> {
                 if self.eof() {
                     return Err(self.loc_error("Unterminated string literal".to_string()));
                 }
+                if self.peek() == '\n' {
+                    self.line += 1;
+                    self.line_start = self.pos + 1;
+                }
                 content.push(self.peek() as PSChar);
                 self.next_char();
+            } else if self.peek() == '\\' && self.can_peek2() && self.peek2().is_whitespace() {
+                self.skip_string_gap()?;
             } else {
                 content.push(self.parse_possibly_escaped_char()?);
             }
@@ -676,12 +1053,34 @@ impl<'a> Lexer<'a> {
         Ok(PSString(content))
     }
 
+    /// Skips a string gap: a backslash, whitespace (possibly including newlines) and another
+    /// backslash.
+    fn skip_string_gap(&mut self) -> Result<(), Error> {
+        self.next_char();
+        while !self.eof() && self.peek().is_whitespace() {
+            if self.peek() == '\n' {
+                self.line += 1;
+                self.line_start = self.pos + 1;
+            }
+            self.next_char();
+        }
+        if self.eof() {
+            return Err(self.loc_error("Unterminated string literal".to_string()));
+        }
+        if self.peek() != '\\' {
+            return Err(self.loc_error("Invalid string gap".to_string()));
+        }
+        self.next_char();
+        Ok(())
+    }
+
     fn parse_possibly_escaped_char(&mut self) -> Result<PSChar, Error> {
         if self.eof() {
             return Err(self.loc_error("Unterminated string literal".to_string()));
         }
         let c = match self.peek() {
             '\\' => {
+                let escape_start = self.pos;
                 self.next_char();
                 if self.eof() {
                     return Err(self.loc_error("Unterminated string literal".to_string()));
@@ -718,7 +1117,17 @@ impl<'a> Lexer<'a> {
                         }
                         return Ok(value);
                     }
-                    c => c as PSChar,
+                    c @ ('"' | '\'' | '\\') => c as PSChar,
+                    c => {
+                        return Err(Error::new(
+                            escape_start,
+                            self.pos + c.len_utf8(),
+                            ErrorKind::Error(LexerError(format!(
+                                "Invalid character escape: \\{}",
+                                c
+                            ))),
+                        ));
+                    }
                 }
             }
             c => c as PSChar,
@@ -774,6 +1183,8 @@ fn ident_to_token(ident: &str, is_upper: bool) -> Token {
                 }
             } else {
                 match str.rsplit_once('.') {
+                    Some((module, "ado")) => Token::QualifiedAdo(module.into()),
+                    Some((module, "do")) => Token::QualifiedDo(module.into()),
                     Some((module, name)) => {
                         Token::QualifiedLowerIdentifier((module.into(), name.into()))
                     }
@@ -810,8 +1221,8 @@ fn operator_to_token(s: &str) -> Token {
     }
 }
 
-fn digit_value(c: char) -> usize {
-    c as usize - '0' as usize
+fn digit_value(c: char) -> i64 {
+    c as i64 - '0' as i64
 }
 
 fn hex_value(c: char) -> u8 {
@@ -853,8 +1264,10 @@ mod tests {
     use insta::{assert_debug_snapshot, assert_snapshot};
     use test_generator::test_resources;
 
-    use super::{Token, TokenInfo};
+    use super::{lex_with_comments, Token, TokenInfo};
+    use crate::ast::{Comment, CommentKind};
     use crate::errors::{Error, ErrorKind, LexerError};
+    use crate::string::PSChar;
 
     fn init() {
         let _ = env_logger::builder().is_test(true).try_init();
@@ -878,7 +1291,61 @@ mod tests {
 
     fn lex(input: &str) -> Result<Vec<Token>, Error> {
         init();
-        try_collect(super::make_lexer(input).map(|v| v.map(|v| v.token)))
+        try_collect(super::make_lexer(input, Default::default(), None).map(|v| v.map(|v| v.token)))
+    }
+
+    fn lex_allowing_tabs(input: &str) -> (Result<Vec<Token>, Error>, Vec<Error>) {
+        init();
+        let mut warnings = vec![];
+        let options = super::LexOptions {
+            allow_tabs: true,
+            ..Default::default()
+        };
+        let result = try_collect(
+            super::make_lexer(input, options, Some(&mut warnings)).map(|v| v.map(|v| v.token)),
+        );
+        (result, warnings)
+    }
+
+    #[test]
+    fn test_tabs_rejected_by_default() {
+        let input = "module Test where\n\tx = 1\n";
+        let tab = input.find('\t').unwrap();
+        test_lex(
+            input,
+            Err(Error::new(
+                tab,
+                tab + 1,
+                ErrorKind::Error(LexerError(
+                    "Tab characters are not allowed in indentation".to_string(),
+                )),
+            )),
+        );
+    }
+
+    #[test]
+    fn test_tabs_allowed() {
+        let (result, warnings) = lex_allowing_tabs("module Test where\n\tx = 1\n\ty = 2\n");
+        assert_eq!(result, lex("module Test where\n x = 1\n y = 2\n"));
+        assert_eq!(warnings, vec![]);
+    }
+
+    #[test]
+    fn test_mixed_tabs_and_spaces_in_block() {
+        let input = "module Test where\n\tx = 1\n y = 2\n";
+        let (result, warnings) = lex_allowing_tabs(input);
+        assert!(result.is_ok());
+        let y = input.find('y').unwrap();
+        assert_eq!(
+            warnings,
+            vec![Error::new(
+                y,
+                y + 1,
+                ErrorKind::Error(LexerError(
+                    "Layout block mixes tabs and spaces in indentation".to_string(),
+                )),
+            )]
+        );
     }
 
     #[test]
@@ -900,12 +1367,101 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_identifier_primes_and_digits() {
+        for ident in ["x'", "x''", "loop1", "foo'bar", "fromInt2"] {
+            test_lex(ident, Ok(vec![Token::LowerIdentifier(ident.to_string())]));
+        }
+        // A leading digit starts a numeric literal instead
+        test_lex(
+            "1x",
+            Err(Error::new(
+                0,
+                2,
+                ErrorKind::Error(LexerError(
+                    "Unexpected character after numeric literal".to_string(),
+                )),
+            )),
+        );
+    }
+
     #[test]
     fn test_integer_literal() {
         test_lex("123", Ok(vec![Token::IntegerLiteral(123)]));
         test_lex("35_000_000", Ok(vec![Token::IntegerLiteral(35_000_000)]));
     }
 
+    #[test]
+    fn test_float_literal() {
+        test_lex("1.5", Ok(vec![Token::FloatLiteral("1.5".into())]));
+        test_lex("6.022e23", Ok(vec![Token::FloatLiteral("6.022e23".into())]));
+        test_lex("1.0e-9", Ok(vec![Token::FloatLiteral("1.0e-9".into())]));
+        test_lex("2E+3", Ok(vec![Token::FloatLiteral("2E+3".into())]));
+        test_lex(
+            "2 elem",
+            Ok(vec![
+                Token::IntegerLiteral(2),
+                Token::LowerIdentifier("elem".into()),
+            ]),
+        );
+        // A leading digit is required
+        test_lex(".5", Ok(vec![Token::Dot, Token::IntegerLiteral(5)]));
+    }
+
+    #[test]
+    fn test_numeric_literal_followed_by_identifier() {
+        let error = |end| {
+            Err(Error::new(
+                0,
+                end,
+                ErrorKind::Error(LexerError(
+                    "Unexpected character after numeric literal".to_string(),
+                )),
+            ))
+        };
+        test_lex("1foo", error(4));
+        test_lex("1.0x", error(4));
+        test_lex("3.14abc", error(7));
+    }
+
+    #[test]
+    fn test_float_literal_errors() {
+        test_lex(
+            "1.",
+            Err(Error::new(
+                0,
+                2,
+                ErrorKind::Error(LexerError(
+                    "Expected digits after the decimal point".to_string(),
+                )),
+            )),
+        );
+        test_lex(
+            "1.5e-",
+            Err(Error::new(
+                0,
+                5,
+                ErrorKind::Error(LexerError("Expected digits in the exponent".to_string())),
+            )),
+        );
+    }
+
+    #[test]
+    fn test_integer_literal_too_large() {
+        test_lex(
+            "9223372036854775807",
+            Ok(vec![Token::IntegerLiteral(i64::MAX)]),
+        );
+        test_lex(
+            "x 9223372036854775808",
+            Err(Error::new(
+                2,
+                21,
+                ErrorKind::Error(LexerError("Integer literal is too large".to_string())),
+            )),
+        );
+    }
+
     #[test]
     fn test_string_literal() {
         test_lex(r#" "" "#, Ok(vec![Token::StringLiteral("".into())]));
@@ -949,6 +1505,26 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_raw_string_literal_multiline() {
+        let input = "x = \"\"\"a\n\"b\"\n\"\"\"\ny";
+        let tokens = try_collect(super::make_lexer(input, Default::default(), None)).unwrap();
+        assert_eq!(tokens[2].token, Token::StringLiteral("a\n\"b\"\n".into()));
+        assert_eq!((tokens[3].line, tokens[3].column), (3, 0));
+    }
+
+    #[test]
+    fn test_raw_string_literal_unterminated() {
+        test_lex(
+            r#" """abc"" "#,
+            Err(Error::new(
+                1,
+                10,
+                ErrorKind::Error(LexerError("Unterminated string literal".to_string())),
+            )),
+        );
+    }
+
     #[test]
     fn test_string_literal_errors() {
         test_lex(
@@ -969,11 +1545,90 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_string_escapes() {
+        test_lex(
+            r#" "\t\r\n\"\'\\" "#,
+            Ok(vec![Token::StringLiteral("\t\r\n\"'\\".into())]),
+        );
+        test_lex(
+            r#" "\x41\x1F600" "#,
+            Ok(vec![Token::StringLiteral("A\u{1F600}".into())]),
+        );
+        test_lex(
+            r#" "ab\qc" "#,
+            Err(Error::new(
+                4,
+                6,
+                ErrorKind::Error(LexerError("Invalid character escape: \\q".to_string())),
+            )),
+        );
+    }
+
+    #[test]
+    fn test_lex_without_layout() {
+        let input = indoc!(
+            "
+            main = do
+              foo
+              bar
+            "
+        );
+        let is_layout = |token: &Token| {
+            matches!(
+                token,
+                Token::LayoutStart | Token::LayoutSep | Token::LayoutEnd
+            )
+        };
+        let with_layout = lex(input).unwrap();
+        let without_layout = try_collect(super::lex_without_layout(input))
+            .unwrap()
+            .into_iter()
+            .map(|(_, token, _)| token)
+            .collect::<Vec<_>>();
+        assert!(with_layout.iter().any(is_layout));
+        assert!(!without_layout.iter().any(is_layout));
+        assert_eq!(
+            with_layout
+                .into_iter()
+                .filter(|token| !is_layout(token))
+                .collect::<Vec<_>>(),
+            without_layout
+        );
+    }
+
+    #[test]
+    fn test_string_gap() {
+        test_lex(
+            r#" "foo\   \bar" "#,
+            Ok(vec![Token::StringLiteral("foobar".into())]),
+        );
+        let input = "x = \"foo\\\n     \\bar\" y\nz";
+        let tokens = try_collect(super::make_lexer(input, Default::default(), None)).unwrap();
+        assert_eq!(tokens[2].token, Token::StringLiteral("foobar".into()));
+        assert_eq!((tokens[2].line, tokens[2].column), (0, 4));
+        let y = input.find('y').unwrap();
+        assert_eq!(
+            (tokens[3].start, tokens[3].line, tokens[3].column),
+            (y, 1, 11)
+        );
+        let z = input.find('z').unwrap();
+        assert_eq!(
+            (tokens[4].start, tokens[4].line, tokens[4].column),
+            (z, 2, 0)
+        );
+    }
+
     #[test]
     fn test_keywords() {
         test_lex("then", Ok(vec![Token::Then]));
         test_lex("do", Ok(vec![Token::Do]));
         test_lex("ado", Ok(vec![Token::Ado]));
+        test_lex("M.do", Ok(vec![Token::QualifiedDo("M".into())]));
+        test_lex(
+            "Control.Monad.ado",
+            Ok(vec![Token::QualifiedAdo("Control.Monad".into())]),
+        );
     }
 
     #[test]
@@ -1045,6 +1700,86 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_nested_multiline_comment() {
+        test_lex(
+            "if {- outer {- inner -} still outer -} 1",
+            Ok(vec![Token::If, Token::IntegerLiteral(1)]),
+        );
+        test_lex("{--} 1 {-{--}-}", Ok(vec![Token::IntegerLiteral(1)]));
+    }
+
+    #[test]
+    fn test_unterminated_multiline_comment() {
+        test_lex(
+            "if {- outer {- inner -} 1",
+            Err(Error::new(
+                3,
+                25,
+                ErrorKind::Error(LexerError("Unterminated block comment".to_string())),
+            )),
+        );
+    }
+
+    #[test]
+    fn test_collect_comments() {
+        let input = "x -- line\n{- block {- nested -} -} y";
+        let mut comments = vec![];
+        let tokens = lex_with_comments(input, Default::default(), &mut vec![], &mut comments)
+            .map(|t| t.unwrap().1)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            tokens,
+            vec![
+                Token::LowerIdentifier("x".into()),
+                Token::LowerIdentifier("y".into()),
+            ]
+        );
+        assert_eq!(
+            comments,
+            vec![
+                (2, Comment("line".into(), CommentKind::Ordinary), 9),
+                (
+                    10,
+                    Comment("block {- nested -}".into(), CommentKind::Ordinary),
+                    34
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_doc_comments() {
+        let input = indoc!(
+            "
+            -- | Doc
+            -- |   indented
+            -- plain
+            {- | Block
+              doc -}
+            {- block -}
+            x
+            "
+        );
+        let mut comments = vec![];
+        for token in lex_with_comments(input, Default::default(), &mut vec![], &mut comments) {
+            token.unwrap();
+        }
+        assert_eq!(
+            comments
+                .into_iter()
+                .map(|(_, comment, _)| comment)
+                .collect::<Vec<_>>(),
+            vec![
+                Comment(" Doc".into(), CommentKind::Doc),
+                Comment("   indented".into(), CommentKind::Doc),
+                Comment("plain".into(), CommentKind::Ordinary),
+                Comment(" Block\n  doc ".into(), CommentKind::Doc),
+                Comment("block".into(), CommentKind::Ordinary),
+            ]
+        );
+    }
+
     #[test]
     fn test_comment_start_at_eof() {
         assert_debug_snapshot!(
@@ -1093,7 +1828,7 @@ mod tests {
 
     fn print_layout(input: &str) -> String {
         init();
-        let result = try_collect(super::make_lexer(input)).unwrap();
+        let result = try_collect(super::make_lexer(input, Default::default(), None)).unwrap();
         // We need to preserve trailing whitespace.
         // Since layout tokens have broken positions (which arguably we could fix, but it's not
         // done currently), we find the last "real" token and use that.
@@ -1818,6 +2553,37 @@ mod tests {
         "###);
     }
 
+    #[test]
+    fn test_utf8_operator_in_infix_expression() {
+        test_lex(
+            "f ≡ g ∘ h -> x",
+            Ok(vec![
+                Token::LowerIdentifier("f".into()),
+                Token::Operator("≡".into()),
+                Token::LowerIdentifier("g".into()),
+                Token::Operator("∘".into()),
+                Token::LowerIdentifier("h".into()),
+                Token::Arrow,
+                Token::LowerIdentifier("x".into()),
+            ]),
+        );
+        // Reserved sequences get their own tokens rather than `Operator`
+        test_lex(
+            "a -> b => c :: d <- e",
+            Ok(vec![
+                Token::LowerIdentifier("a".into()),
+                Token::Arrow,
+                Token::LowerIdentifier("b".into()),
+                Token::FatArrow,
+                Token::LowerIdentifier("c".into()),
+                Token::TypeOf,
+                Token::LowerIdentifier("d".into()),
+                Token::Bind,
+                Token::LowerIdentifier("e".into()),
+            ]),
+        );
+    }
+
     #[test]
     fn test_unicode_syntax() {
         assert_debug_snapshot!(
@@ -1834,6 +2600,111 @@ mod tests {
         "###);
     }
 
+    #[test]
+    fn test_ascii_only_syntax() {
+        let options = super::LexOptions {
+            ascii_only_syntax: true,
+            ..Default::default()
+        };
+        let result =
+            try_collect(super::make_lexer("∀ :: ∷", options, None).map(|v| v.map(|v| v.token)));
+        assert_eq!(
+            result,
+            Ok(vec![
+                Token::Operator("∀".into()),
+                Token::TypeOf,
+                Token::Operator("∷".into()),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_type_application_at() {
+        test_lex(
+            "f @Int x@Nothing",
+            Ok(vec![
+                Token::LowerIdentifier("f".into()),
+                Token::TypeApp,
+                Token::UpperIdentifier("Int".into()),
+                Token::LowerIdentifier("x".into()),
+                Token::At,
+                Token::UpperIdentifier("Nothing".into()),
+            ]),
+        );
+    }
+
+    #[test]
+    fn test_char_literal() {
+        test_lex(r"'a'", Ok(vec![Token::CharLiteral('a' as PSChar)]));
+        test_lex(r"'\n'", Ok(vec![Token::CharLiteral('\n' as PSChar)]));
+        test_lex(r"'\\'", Ok(vec![Token::CharLiteral('\\' as PSChar)]));
+        test_lex(r"'\''", Ok(vec![Token::CharLiteral('\'' as PSChar)]));
+        test_lex(r"'\x1F600'", Ok(vec![Token::CharLiteral(0x1F600)]));
+    }
+
+    #[test]
+    fn test_holes() {
+        test_lex(
+            "f ?x ?_ ?help'",
+            Ok(vec![
+                Token::LowerIdentifier("f".into()),
+                Token::Hole("x".into()),
+                Token::Hole("_".into()),
+                Token::Hole("help'".into()),
+            ]),
+        );
+        // `?` inside an operator is not a hole
+        test_lex(
+            "a <?> b",
+            Ok(vec![
+                Token::LowerIdentifier("a".into()),
+                Token::Operator("<?>".into()),
+                Token::LowerIdentifier("b".into()),
+            ]),
+        );
+        test_lex(
+            "f ? x",
+            Err(Error::new(
+                2,
+                3,
+                ErrorKind::Error(LexerError("Expected a hole name after `?`".to_string())),
+            )),
+        );
+        test_lex(
+            "?x.y",
+            Ok(vec![
+                Token::Hole("x".into()),
+                Token::Dot,
+                Token::LowerIdentifier("y".into()),
+            ]),
+        );
+        test_lex(
+            "f ?M.x",
+            Err(Error::new(
+                2,
+                6,
+                ErrorKind::Error(LexerError("Hole names can't be qualified".to_string())),
+            )),
+        );
+    }
+
+    #[test]
+    fn test_char_literal_errors() {
+        let error = |start, end, message: &str| {
+            Err(Error::new(
+                start,
+                end,
+                ErrorKind::Error(LexerError(message.to_string())),
+            ))
+        };
+        test_lex("x ''", error(2, 4, "Empty character literal"));
+        test_lex(
+            "x 'ab' y",
+            error(2, 6, "Character literal must contain exactly one character"),
+        );
+        test_lex("x 'ab\n", error(2, 5, "Unterminated character literal"));
+    }
+
     #[test]
     fn test_unicode_escapes() {
         assert_debug_snapshot!(
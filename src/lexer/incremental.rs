@@ -0,0 +1,254 @@
+//! Lexing of an edited input, reusing the tokens of the previous version outside the edit.
+
+use super::{make_lexer, resume_lexer_after, LexOptions, Token, TokenInfo};
+use crate::errors::Error;
+use std::iter::once;
+
+/// An edit replacing whole lines: lines `start_line..old_end_line` of the old input were replaced
+/// by lines `start_line..new_end_line` of the new input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LineEdit {
+    pub start_line: usize,
+    pub old_end_line: usize,
+    pub new_end_line: usize,
+}
+
+/// Lexes `new_input`, an edited version of `old_input`, given `old_tokens`, the result of lexing
+/// `old_input`. The result is the same as lexing `new_input` from scratch.
+///
+/// Only the edited lines are lexed again and the tokens after them are shifted, as long as the
+/// layout algorithm sees the edited lines the same way as before. Otherwise the whole input is
+/// lexed again.
+pub fn relex(
+    old_input: &str,
+    old_tokens: &[TokenInfo],
+    new_input: &str,
+    edit: LineEdit,
+) -> Result<Vec<TokenInfo>, Error> {
+    match relex_lines(old_input, old_tokens, new_input, edit) {
+        Some(tokens) => Ok(tokens),
+        None => make_lexer(new_input, LexOptions::default(), None).collect(),
+    }
+}
+
+/// Re-lexes only the edited lines. Returns `None` if the whole input has to be lexed again.
+fn relex_lines(
+    old_input: &str,
+    old_tokens: &[TokenInfo],
+    new_input: &str,
+    edit: LineEdit,
+) -> Option<Vec<TokenInfo>> {
+    let start = line_offset(new_input, edit.start_line);
+    let old_end = line_offset(old_input, edit.old_end_line);
+    let new_end = line_offset(new_input, edit.new_end_line);
+    let old_lines = old_input.get(start..old_end)?;
+    let new_lines = new_input.get(start..new_end)?;
+    // Comments are not tokens, so we can't tell where one ends without lexing from its start
+    if [old_lines, new_lines]
+        .iter()
+        .any(|lines| lines.contains("{-") || lines.contains("-}"))
+    {
+        return None;
+    }
+
+    // The last token before the edit, which lexing resumes after, and the first one after it
+    let prev = old_tokens
+        .iter()
+        .rposition(|t| !is_layout(&t.token) && t.start < start);
+    let next = old_tokens
+        .iter()
+        .position(|t| !is_layout(&t.token) && t.start >= old_end)?;
+    let old_next = &old_tokens[next];
+    let first = prev.map_or(0, |prev| prev + 1);
+    let old_edited = old_tokens[first..next]
+        .iter()
+        .filter(|t| !is_layout(&t.token))
+        .collect::<Vec<_>>();
+    if old_edited.last().is_some_and(|t| t.end > old_end) {
+        return None;
+    }
+
+    let mut lexer = match prev {
+        Some(prev) => {
+            let prev = &old_tokens[prev];
+            if prev.end > start || old_input[prev.start..prev.end].contains('\n') {
+                return None;
+            }
+            resume_lexer_after(new_input, prev)
+        }
+        None => {
+            let mut lexer = make_lexer(new_input, LexOptions::default(), None);
+            lexer.layout = false;
+            lexer
+        }
+    };
+    let mut new_edited = vec![];
+    let new_next = loop {
+        let token = lexer.next()?.ok()?;
+        if token.start >= new_end {
+            break token;
+        }
+        if token.end > new_end || new_input[token.start..token.end].contains('\n') {
+            return None;
+        }
+        new_edited.push(token);
+    };
+
+    let shift = |offset: usize| offset - old_end + new_end;
+    if new_next.token != old_next.token
+        || new_next.start != shift(old_next.start)
+        || new_next.end != shift(old_next.end)
+        || old_edited.len() != new_edited.len()
+        || !old_edited
+            .iter()
+            .copied()
+            .chain(once(old_next))
+            .zip(new_edited.iter().chain(once(&new_next)))
+            .all(|(old, new)| same_layout(old, new))
+    {
+        return None;
+    }
+
+    let new_next_line = new_next.line;
+    let mut tokens = old_tokens[..first].to_vec();
+    let mut new_edited = new_edited.into_iter().chain(once(new_next)).peekable();
+    for token in &old_tokens[first..=next] {
+        if is_layout(&token.token) {
+            let following = new_edited.peek().expect("should end with a real token");
+            tokens.push(layout_token(token.token.clone(), following));
+        } else {
+            tokens.extend(new_edited.next());
+        }
+    }
+    tokens.extend(old_tokens[next + 1..].iter().map(|token| TokenInfo {
+        leading_space_start: shift(token.leading_space_start),
+        start: shift(token.start),
+        end: shift(token.end),
+        trailing_space_end: shift(token.trailing_space_end),
+        line: token.line - old_next.line + new_next_line,
+        ..token.clone()
+    }));
+    Some(tokens)
+}
+
+/// Whether the layout algorithm treats the two tokens the same way. It looks only at the
+/// positions of tokens and the kinds of some of them.
+fn same_layout(old: &TokenInfo, new: &TokenInfo) -> bool {
+    let neutral = is_layout_neutral(&old.token);
+    neutral == is_layout_neutral(&new.token)
+        && (neutral || old.token == new.token)
+        && old.column == new.column
+        && old.indent_level == new.indent_level
+}
+
+/// Tokens whose kind the layout algorithm never looks at.
+fn is_layout_neutral(token: &Token) -> bool {
+    matches!(
+        token,
+        Token::IntegerLiteral(_)
+            | Token::FloatLiteral(_)
+            | Token::StringLiteral(_)
+            | Token::CharLiteral(_)
+            | Token::LowerIdentifier(_)
+            | Token::QualifiedLowerIdentifier(_)
+            | Token::UpperIdentifier(_)
+            | Token::QualifiedUpperIdentifier(_)
+            | Token::Hole(_)
+            | Token::True
+            | Token::False
+            | Token::Wildcard
+    )
+}
+
+fn is_layout(token: &Token) -> bool {
+    matches!(
+        token,
+        Token::LayoutStart | Token::LayoutSep | Token::LayoutEnd
+    )
+}
+
+/// A layout token inserted before `following`, positioned like the lexer positions them.
+fn layout_token(token: Token, following: &TokenInfo) -> TokenInfo {
+    TokenInfo {
+        token,
+        end: following.trailing_space_end,
+        ..following.clone()
+    }
+}
+
+/// Offset of the start of the given (zero-based) line, or the end of input if there are fewer
+/// lines.
+fn line_offset(input: &str, line: usize) -> usize {
+    match line {
+        0 => 0,
+        _ => input
+            .match_indices('\n')
+            .nth(line - 1)
+            .map_or(input.len(), |(i, _)| i + 1),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indoc::indoc;
+
+    const INPUT: &str = indoc!(
+        "
+        module Test where
+        f x = do
+          y <- g x
+          pure y
+          where
+          g = h
+        k = 1
+        "
+    );
+
+    fn lex(input: &str) -> Vec<TokenInfo> {
+        make_lexer(input, LexOptions::default(), None)
+            .collect::<Result<_, _>>()
+            .unwrap()
+    }
+
+    fn edit_line(line: usize, replacement: &str) -> (String, LineEdit) {
+        let mut lines = INPUT.lines().collect::<Vec<_>>();
+        lines[line] = replacement;
+        let edit = LineEdit {
+            start_line: line,
+            old_end_line: line + 1,
+            new_end_line: line + 1,
+        };
+        (lines.join("\n") + "\n", edit)
+    }
+
+    #[test]
+    fn edit_one_line() {
+        let old_tokens = lex(INPUT);
+        let (new_input, edit) = edit_line(2, "  y <- g xs");
+        assert_eq!(
+            relex_lines(INPUT, &old_tokens, &new_input, edit),
+            Some(lex(&new_input))
+        );
+    }
+
+    #[test]
+    fn edit_changing_layout_lexes_everything() {
+        let old_tokens = lex(INPUT);
+        for replacement in ["y <- g x", "  y <- g x where", "  yy <- g x"] {
+            let (new_input, edit) = edit_line(2, replacement);
+            assert_eq!(
+                relex_lines(INPUT, &old_tokens, &new_input, edit),
+                None,
+                "{}",
+                replacement
+            );
+            assert_eq!(
+                relex(INPUT, &old_tokens, &new_input, edit),
+                Ok(lex(&new_input)),
+                "{}",
+                replacement
+            );
+        }
+    }
+}
@@ -16,6 +16,7 @@ extern crate lalrpop_util;
 #[salsa::jar(db = Db)]
 pub struct Jar(
     crate::ModuleSource,
+    crate::CompilerConfig,
     crate::ModuleId,
     crate::parsed_module,
     crate::renamed_module::renamed_module,
@@ -46,15 +47,31 @@ pub struct ModuleSource {
     contents: Option<(PathBuf, String)>,
 }
 
+/// Toggles affecting how sources are parsed. There is a single instance, created by
+/// `Database::new`; queries reading a field are recomputed when it is set.
+#[salsa::input]
+pub struct CompilerConfig {
+    pub parse_options: crate::parser::ParseOptions,
+}
+
 #[salsa::accumulator]
 pub struct Diagnostics(Diagnostic);
 
 #[derive(Clone, Debug, new, PartialEq, Eq)]
 pub struct Diagnostic {
     pub span: SourceSpan,
+    #[new(value = "Severity::Error")]
+    pub severity: Severity,
     pub message: String,
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    /// Doesn't prevent compilation, e.g. a lexer problem accepted because of `ParseOptions`
+    Warning,
+}
+
 #[derive(Clone, Debug, new, PartialEq, Eq)]
 pub struct DiagnosticFmt {
     pub span: SourceSpan,
@@ -171,17 +188,23 @@ impl<T: std::fmt::Debug, E: ToDiagnostic> ToDiagnostic for lalrpop_util::ParseEr
                 Diagnostic::new(source_span, "invalid token".into())
             }
 
-            UnrecognizedEOF { location, .. } => {
+            UnrecognizedEOF { location, expected } => {
                 let source_span = SourceSpan::new_in_module(*location, *location, module_id);
-                Diagnostic::new(source_span, "unexpected eof".into())
+                Diagnostic::new(
+                    source_span,
+                    format!("unexpected end of input{}", expected_tokens(expected)),
+                )
             }
 
             UnrecognizedToken {
                 token: (start, token, end),
-                ..
+                expected,
             } => {
                 let source_span = SourceSpan::new_in_module(*start, *end, module_id);
-                Diagnostic::new(source_span, format!("unrecognized token {:?}", token))
+                Diagnostic::new(
+                    source_span,
+                    format!("unexpected token {:?}{}", token, expected_tokens(expected)),
+                )
             }
             ExtraToken {
                 token: (start, token, end),
@@ -194,6 +217,15 @@ impl<T: std::fmt::Debug, E: ToDiagnostic> ToDiagnostic for lalrpop_util::ParseEr
     }
 }
 
+/// Formats the terminals LALRPOP reports as acceptable, e.g. `, expected one of "where", "("`.
+fn expected_tokens(expected: &[String]) -> String {
+    match expected {
+        [] => String::new(),
+        [token] => format!(", expected {}", token),
+        _ => format!(", expected one of {}", expected.join(", ")),
+    }
+}
+
 impl<T, E> ToSourceSpan for lalrpop_util::ParseError<usize, T, E> {
     fn to_source_span(&self, module_id: ModuleId) -> SourceSpan {
         use lalrpop_util::ParseError::*;
@@ -234,19 +266,24 @@ pub fn parsed_module(db: &dyn Db, module: ModuleId) -> ParsedModule {
         .contents(db)
         .as_ref()
         .unwrap_or_else(|| panic!("module not found: {:?}", module.name(db)));
-    let (errs, result) = crate::parser::parse_module(db, input, module);
-    errs.iter().for_each(|err| {
-        Diagnostics::push(db, err.to_diagnostic(module));
+    let options = db.compiler_config().parse_options(db);
+    let mut warnings = vec![];
+    let parse_result =
+        crate::parser::parse_module_with_options(db, input, module, options, &mut warnings);
+    for diagnostic in crate::parser::parse_diagnostics(module, &parse_result) {
+        Diagnostics::push(db, diagnostic);
+    }
+    let (_, result) = parse_result;
+    warnings.iter().for_each(|warning| {
+        let mut diagnostic = warning.to_diagnostic(module);
+        diagnostic.severity = Severity::Warning;
+        Diagnostics::push(db, diagnostic);
     });
 
     ParsedModule {
         filename: filename.clone(),
         ast: match result {
-            Err(err) => {
-                Diagnostics::push(db, err.to_diagnostic(module));
-
-                declarations::corrupted(db, err.to_source_span(module))
-            }
+            Err(err) => declarations::corrupted(db, err.to_source_span(module)),
             Ok(module) => module,
         },
     }
@@ -254,12 +291,14 @@ pub fn parsed_module(db: &dyn Db, module: ModuleId) -> ParsedModule {
 
 pub trait Db: salsa::DbWithJar<Jar> {
     fn module_source(&self, module: ModuleId) -> ModuleSource;
+    fn compiler_config(&self) -> CompilerConfig;
 }
 
 #[salsa::db(Jar)]
 pub struct Database {
     storage: salsa::Storage<Self>,
     module_sources: Arc<DashMap<String, ModuleSource>>,
+    compiler_config: Option<CompilerConfig>,
 }
 
 #[derive(Debug, Error)]
@@ -278,7 +317,9 @@ impl Database {
         let mut db = Self {
             storage,
             module_sources: Arc::new(DashMap::new()),
+            compiler_config: None,
         };
+        db.compiler_config = Some(CompilerConfig::new(&db, Default::default()));
         db.add_source_file("<builtin>/Prim.purs".into(), PRIM_SOURCE.into())
             .expect("Prim should have a module name");
         db
@@ -364,15 +405,38 @@ impl Database {
             .unwrap();
         db
     }
+
+    /// Builds a database from `(filename, contents)` pairs. Module names are taken from the
+    /// module headers.
+    #[cfg(test)]
+    pub fn test_multi_file_db(files: &[(&str, &str)]) -> Self {
+        let mut db = Self::new();
+        for (filename, contents) in files {
+            db.add_source_file(filename.into(), contents.to_string())
+                .unwrap();
+        }
+        db
+    }
 }
 
 impl Db for Database {
     fn module_source(&self, module: ModuleId) -> ModuleSource {
         match self.module_sources.entry(module.name(self)) {
             Entry::Occupied(entry) => *entry.get(),
-            Entry::Vacant(entry) => *entry.insert(ModuleSource::new(self, module, None)),
+            Entry::Vacant(entry) => {
+                let contents = crate::prim::builtin_module_source(entry.key()).map(|source| {
+                    let filename = format!("<builtin>/{}.purs", entry.key());
+                    (filename.into(), source.to_string())
+                });
+                *entry.insert(ModuleSource::new(self, module, contents))
+            }
         }
     }
+
+    fn compiler_config(&self) -> CompilerConfig {
+        self.compiler_config
+            .expect("compiler config is created in Database::new")
+    }
 }
 
 impl salsa::Database for Database {}
@@ -382,6 +446,7 @@ impl ParallelDatabase for Database {
         salsa::Snapshot::new(Self {
             storage: self.storage.snapshot(),
             module_sources: self.module_sources.clone(),
+            compiler_config: self.compiler_config,
         })
     }
 }
@@ -396,7 +461,11 @@ impl Clone for DbSnapshot {
 
 pub mod ast;
 pub mod codegen;
+pub mod desugar;
+pub mod doc_comments;
 pub mod errors;
+pub mod fixity;
+pub mod free_vars;
 pub mod indexed_module;
 pub mod lexer;
 pub mod parser;
@@ -405,9 +474,33 @@ pub mod prim;
 pub mod rename;
 pub mod renamed_module;
 pub mod scc;
+pub mod scope;
+#[cfg(feature = "serde")]
+pub mod serialize;
 pub mod source_span;
 pub mod string;
+pub mod string_literals;
 pub mod symbol;
 pub mod token;
 pub mod typecheck;
 pub mod utils;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn changing_config_reparses_modules() {
+        let mut db = Database::test_single_file_db("module Test where\nx =\n\t1\n");
+        let module = ModuleId::new(&db, "Test".into());
+        let diagnostics = |db: &Database| parsed_module::accumulated::<Diagnostics>(db, module);
+        assert_ne!(diagnostics(&db), vec![]);
+
+        let options = crate::parser::ParseOptions {
+            allow_tabs: true,
+            ..Default::default()
+        };
+        db.compiler_config().set_parse_options(&mut db).to(options);
+        assert_eq!(diagnostics(&db), vec![]);
+    }
+}
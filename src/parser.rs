@@ -1,13 +1,17 @@
-use crate::ast::InfixOp;
+use crate::ast::CaseBranch;
+use crate::ast::Comment;
+use crate::ast::Fundep;
 use crate::ast::Literal;
 use crate::ast::Located;
 use crate::ast::Pat;
 use crate::ast::PatKind;
 use crate::ast::TypeParameter;
-use crate::ast::{Expr, ExprKind, Module, Type};
+use crate::ast::{Declaration, Expr, ExprKind, Module, Type};
+use crate::ast::{InfixOp, InfixOpKind, InstanceDeclaration};
 use crate::ast::{QualifiedName, TypeKind};
-use crate::errors::Error;
+use crate::errors::{Error, ErrorKind};
 use crate::lexer;
+use crate::source_span::SourceSpan;
 use crate::symbol::Symbol;
 use crate::token::Token;
 use crate::ModuleId;
@@ -19,6 +23,8 @@ lalrpop_mod!(
     pub parser
 );
 
+/// Splits an instance head `C a b` into the class and its arguments. The class may be referred to
+/// by a qualified name, `M.C a b`, resolved by the renamer like any other type name.
 fn constraint_to_instance_head(c: Type) -> Option<(QualifiedName, Vec<Type>)> {
     let mut t = c;
     let mut args = vec![];
@@ -37,6 +43,8 @@ fn constraint_to_instance_head(c: Type) -> Option<(QualifiedName, Vec<Type>)> {
     }
 }
 
+/// Splits the head of a class declaration into the class name and its parameters. Unlike an
+/// instance head, it declares the class, so it can't be qualified.
 fn constraint_to_class_head(db: &dyn crate::Db, c: Type) -> Option<(Symbol, Vec<TypeParameter>)> {
     let mut t = c;
     let mut params = vec![];
@@ -72,22 +80,251 @@ fn constraint_to_class_head(db: &dyn crate::Db, c: Type) -> Option<(Symbol, Vec<
     }
 }
 
-fn apply_record_updates(f: Expr, args: Vec<Expr>) -> ExprKind {
+/// An argument in an application chain.
+enum AppArg {
+    /// A value argument, or a record update suffix applying to the preceding argument
+    Expr(Expr),
+    /// A visible type argument, `@Type`
+    Type(Type),
+}
+
+/// A field between braces in argument position, before it's known whether they are a record
+/// literal or a record update.
+enum ArgRecordField {
+    /// `x: expr`
+    Literal(Symbol, Expr),
+    /// `x`, standing for `x: x`
+    Pun(Symbol, Expr),
+    /// `x = expr` or a nested update `x { ... }`
+    Update(Symbol, Expr),
+}
+
+/// Braces with an `=` field are a record update, in which every field must be one. Otherwise
+/// they are a record literal argument, e.g. `pure { x, y }`.
+fn record_arg(span: SourceSpan, fields: Vec<ArgRecordField>) -> Result<AppArg, Error> {
+    use ArgRecordField::*;
+    let is_update = fields.iter().any(|field| matches!(field, Update(..)));
+    let kind = if is_update {
+        let updates = fields
+            .into_iter()
+            .map(|field| match field {
+                Update(label, expr) => Ok((label, expr)),
+                Pun(_, expr) => Err(Error::new(
+                    expr.0.start,
+                    expr.0.end,
+                    ErrorKind::PunInRecordUpdate,
+                )),
+                Literal(_, expr) => Err(Error::new(
+                    expr.0.start,
+                    expr.0.end,
+                    ErrorKind::LiteralFieldInRecordUpdate,
+                )),
+            })
+            .collect::<Result<_, _>>()?;
+        ExprKind::RecordUpdateSuffix(updates)
+    } else {
+        let fields = fields
+            .into_iter()
+            .map(|field| match field {
+                Literal(label, expr) | Pun(label, expr) | Update(label, expr) => (label, expr),
+            })
+            .collect();
+        ExprKind::Literal(crate::ast::Literal::Object(fields))
+    };
+    Ok(AppArg::Expr(Located(span, kind)))
+}
+
+/// Builds a record update, desugaring nested updates: `r { a { b = 1 } }` becomes
+/// `r { a = r.a { b = 1 } }`.
+fn record_update(record: Expr, updates: Vec<(Symbol, Expr)>) -> ExprKind {
+    let updates = updates
+        .into_iter()
+        .map(|(label, value)| match value {
+            Located(span, ExprKind::RecordUpdateSuffix(nested)) => {
+                let field = Located(span, ExprKind::Accessor(Box::new(record.clone()), label));
+                (label, Located(span, record_update(field, nested)))
+            }
+            value => (label, value),
+        })
+        .collect();
+    ExprKind::RecordUpdate(Box::new(record), updates)
+}
+
+fn apply_args(f: Expr, args: Vec<AppArg>) -> Result<ExprKind, Error> {
     let mut result = vec![f];
-    for expr in args {
-        match expr {
-            Located(suffix_span, ExprKind::RecordUpdateSuffix(update)) => {
+    for arg in args {
+        match arg {
+            AppArg::Expr(Located(suffix_span, ExprKind::RecordUpdateSuffix(update))) => {
                 let last = result.pop().expect("should be non-empty");
+                result.push(Located(suffix_span, record_update(last, update)));
+            }
+            AppArg::Expr(expr) => result.push(expr),
+            AppArg::Type(type_) => {
+                // Type arguments apply to everything before them: `f x @T` is `(f x) @T`
+                let args = result.split_off(1);
+                let f = result.pop().expect("should be non-empty");
+                let f = match args.last() {
+                    Some(last) => Located(
+                        SourceSpan {
+                            end: last.0.end,
+                            ..f.0
+                        },
+                        ExprKind::App(Box::new(f), args),
+                    ),
+                    None => f,
+                };
+                let span = SourceSpan {
+                    end: type_.0.end,
+                    ..f.0
+                };
                 result.push(Located(
-                    suffix_span,
-                    ExprKind::RecordUpdate(Box::new(last), update),
+                    span,
+                    ExprKind::TypeApp(Box::new(f), Box::new(type_)),
                 ));
             }
-            _ => result.push(expr),
         }
     }
-    let f = result.remove(0);
-    ExprKind::App(Box::new(f), result)
+    Ok(match &result[..] {
+        // Everything was folded into a type application or record update
+        [_] => result.pop().unwrap().1,
+        _ => {
+            let f = result.remove(0);
+            ExprKind::App(Box::new(f), result)
+        }
+    })
+}
+
+/// Checks that every branch of a `case` has one pattern per scrutinee.
+fn check_case_arity(exprs: &[Expr], branches: &[CaseBranch]) -> Result<(), Error> {
+    for branch in branches {
+        if branch.pats.len() != exprs.len() {
+            let first = branch.pats.first().expect("should be non-empty");
+            let last = branch.pats.last().expect("should be non-empty");
+            return Err(Error::new(
+                first.0.start,
+                last.0.end,
+                ErrorKind::CaseArityMismatch {
+                    scrutinees: exprs.len(),
+                    patterns: branch.pats.len(),
+                },
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// A functional dependency as parsed, with the spans of its variables.
+type LocatedFundep = (Vec<Located<Symbol>>, Vec<Located<Symbol>>);
+
+/// Checks that functional dependencies mention only the class parameters, and drops the spans of
+/// their variables.
+fn check_fundeps(
+    db: &dyn crate::Db,
+    params: &[TypeParameter],
+    fundeps: Vec<LocatedFundep>,
+) -> Result<Vec<Fundep>, Error> {
+    let unlocated = |vars: Vec<Located<Symbol>>| -> Result<Vec<Symbol>, Error> {
+        vars.into_iter()
+            .map(|Located(span, var)| {
+                if params.iter().any(|(param, _)| *param == var) {
+                    Ok(var)
+                } else {
+                    Err(Error::new(
+                        span.start,
+                        span.end,
+                        ErrorKind::UnknownFundepVariable(var.text(db).clone()),
+                    ))
+                }
+            })
+            .collect()
+    };
+    fundeps
+        .into_iter()
+        .map(|(from, to)| {
+            Ok(Fundep {
+                from: unlocated(from)?,
+                to: unlocated(to)?,
+            })
+        })
+        .collect()
+}
+
+fn parenthesized_expr(expr: Expr) -> ExprKind {
+    let is_wildcard = |x: &Expr| matches!(x.1, ExprKind::Wildcard);
+    // `_` can stand for an operand of an infix chain, or the function or an argument of an
+    // application
+    let is_section = match &expr.1 {
+        ExprKind::Infix(first, rest) => std::iter::once(&**first)
+            .chain(rest.iter().map(|(_, x)| x))
+            .any(is_wildcard),
+        ExprKind::App(f, args) => is_wildcard(f) || args.iter().any(is_wildcard),
+        _ => false,
+    };
+    if is_section {
+        ExprKind::Section(Box::new(expr))
+    } else {
+        expr.1
+    }
+}
+
+/// `case _ of ...` is a section, with a parameter for each `_` scrutinee.
+fn case_section(case: Expr) -> ExprKind {
+    match &case.1 {
+        ExprKind::Case { exprs, .. } if exprs.iter().any(|x| matches!(x.1, ExprKind::Wildcard)) => {
+            ExprKind::Section(Box::new(case))
+        }
+        _ => case.1,
+    }
+}
+
+/// End of the last real token of an instance chain. The `@R` location after a layout block
+/// points past the virtual `LayoutEnd` token, so it's not suitable for error spans.
+fn instance_chain_end(chain: &[InstanceDeclaration]) -> Option<usize> {
+    let last = chain.last()?;
+    last.body
+        .last()
+        .map(|decl| decl.0.end)
+        .or_else(|| last.args.last().map(|arg| arg.0.end))
+}
+
+/// Turns a section into a lambda, replacing each `_` operand with a fresh parameter (left to
+/// right), e.g. `(_ + 1)` into `\$_0 -> $_0 + 1` and `(map _ xs)` into `\$_0 -> map $_0 xs`.
+/// In `case _, x of ...` only the scrutinees are replaced.
+pub fn desugar_section(db: &dyn crate::Db, section: Expr) -> ExprKind {
+    let Located(span, kind) = section;
+    let mut params = vec![];
+    let mut replace_wildcard = |x: Expr| match x {
+        Located(span, ExprKind::Wildcard) => {
+            let name = Symbol::new(db, format!("$_{}", params.len()));
+            params.push(Located(span, PatKind::Var(name)));
+            Located(
+                span,
+                ExprKind::Var(QualifiedName::new_unqualified(db, name)),
+            )
+        }
+        x => x,
+    };
+    let body = match kind {
+        ExprKind::Infix(first, rest) => {
+            let first = replace_wildcard(*first);
+            let rest = rest
+                .into_iter()
+                .map(|(op, x)| (op, replace_wildcard(x)))
+                .collect();
+            ExprKind::Infix(Box::new(first), rest)
+        }
+        ExprKind::App(f, args) => {
+            let f = replace_wildcard(*f);
+            let args = args.into_iter().map(replace_wildcard).collect();
+            ExprKind::App(Box::new(f), args)
+        }
+        ExprKind::Case { exprs, branches } => ExprKind::Case {
+            exprs: exprs.into_iter().map(replace_wildcard).collect(),
+            branches,
+        },
+        kind => return kind,
+    };
+    ExprKind::Lam(params, Box::new(Located(span, body)))
 }
 
 fn expr_to_pat(db: &dyn crate::Db, expr: Expr) -> Result<Pat, String> {
@@ -95,6 +332,20 @@ fn expr_to_pat(db: &dyn crate::Db, expr: Expr) -> Result<Pat, String> {
     Ok(Located(
         span,
         match kind {
+            ExprKind::Literal(Literal::Object(fields)) => PatKind::Record(
+                fields
+                    .into_iter()
+                    .map(|(label, x)| match x {
+                        // `{ x }` was parsed as `{ x: x }`
+                        Located(_, ExprKind::Var(name))
+                            if name.module(db).is_none() && name.name(db) == label =>
+                        {
+                            Ok((label, None))
+                        }
+                        x => Ok((label, Some(expr_to_pat(db, x)?))),
+                    })
+                    .collect::<Result<_, String>>()?,
+            ),
             ExprKind::Literal(lit) => PatKind::Literal(lit_expr_to_pat(db, lit)?),
             ExprKind::Infix(x, xs) => PatKind::Infix(
                 Box::new(expr_to_pat(db, *x)?),
@@ -110,18 +361,19 @@ fn expr_to_pat(db: &dyn crate::Db, expr: Expr) -> Result<Pat, String> {
                 }
                 PatKind::Var(name.name(db))
             }
-            ExprKind::DataConstructor(name) => PatKind::DataConstructorApp(name, vec![]),
-            ExprKind::App(f, args) => match f.into_inner() {
-                ExprKind::DataConstructor(name) => PatKind::DataConstructorApp(
+            ExprKind::DataConstructor(name) => PatKind::DataConstructorApp(name, vec![], vec![]),
+            ExprKind::App(f, args) => match constructor_with_type_args(&f) {
+                Some((name, type_args)) => PatKind::DataConstructorApp(
                     name,
+                    type_args,
                     args.into_iter()
                         .map(|x| expr_to_pat(db, x))
                         .collect::<Result<_, _>>()?,
                 ),
-                f => {
+                None => {
                     return Err(format!(
                         "illegal pattern in data constructor position: {:?}",
-                        f
+                        f.1
                     ))
                 }
             },
@@ -131,26 +383,52 @@ fn expr_to_pat(db: &dyn crate::Db, expr: Expr) -> Result<Pat, String> {
             ExprKind::Typed(x, ty) => PatKind::Typed(Box::new(expr_to_pat(db, *x)?), ty),
             ExprKind::Let { .. } => return Err("Illegal let in pattern".into()),
             ExprKind::Wildcard => PatKind::Wildcard,
+            ExprKind::Hole(_) => return Err("Illegal hole in pattern".into()),
+            kind @ ExprKind::TypeApp(..) => {
+                match constructor_with_type_args(&Located(span, kind)) {
+                    Some((name, type_args)) => PatKind::DataConstructorApp(name, type_args, vec![]),
+                    None => return Err("Illegal type application in pattern".into()),
+                }
+            }
             ExprKind::RecordUpdateSuffix(_) => {
                 return Err("Illegal record update in pattern".into())
             }
-            ExprKind::Do(_) => return Err("Illegal do in pattern".into()),
-            ExprKind::Ado(_, _) => return Err("Illegal ado in pattern".into()),
+            ExprKind::Do(..) => return Err("Illegal do in pattern".into()),
+            ExprKind::Ado(..) => return Err("Illegal ado in pattern".into()),
             ExprKind::NamedPat(name, x) => PatKind::Named(name, Box::new(expr_to_pat(db, *x)?)),
             ExprKind::Operator(_) => return Err("Illegal operator in pattern".into()),
+            // Only numeric literals can be negative, `-1` is parsed as negation of `1`
             ExprKind::Negate(x) => match x.into_inner() {
-                ExprKind::Literal(Literal::Integer(x)) => PatKind::Literal(Literal::Integer(-x)),
+                ExprKind::Literal(Literal::Integer(x)) => PatKind::Literal(Literal::Integer(
+                    x.checked_neg()
+                        .ok_or("Integer literal is too large in pattern")?,
+                )),
+                ExprKind::Literal(Literal::Float(x)) => PatKind::Literal(Literal::Float(-x)),
                 _ => return Err("Illegal negation in pattern".into()),
             },
+            ExprKind::Section(x) => expr_to_pat(db, *x)?.1,
             ExprKind::Error => PatKind::Error,
         },
     ))
 }
 
+/// Splits `C @A @B` into the constructor and its type arguments.
+fn constructor_with_type_args(expr: &Expr) -> Option<(QualifiedName, Vec<Type>)> {
+    match &expr.1 {
+        ExprKind::DataConstructor(name) => Some((*name, vec![])),
+        ExprKind::TypeApp(f, type_) => {
+            let (name, mut type_args) = constructor_with_type_args(f)?;
+            type_args.push((**type_).clone());
+            Some((name, type_args))
+        }
+        _ => None,
+    }
+}
+
 fn infix_op_to_pat(op: InfixOp) -> Result<QualifiedName, String> {
-    match op {
-        InfixOp::Symbol(s) => Ok(s),
-        InfixOp::Backtick(_) => Err("Illegal backtick operator in pattern".into()),
+    match op.1 {
+        InfixOpKind::Symbol(s) => Ok(s),
+        InfixOpKind::Backtick(_) => Err("Illegal backtick operator in pattern".into()),
     }
 }
 
@@ -166,11 +444,7 @@ fn lit_expr_to_pat(db: &dyn crate::Db, lit: Literal<Expr>) -> Result<Literal<Pat
                 .map(|x| expr_to_pat(db, x))
                 .collect::<Result<_, _>>()?,
         ),
-        Literal::Object(xs) => Literal::Object(
-            xs.into_iter()
-                .map(|(k, x)| Ok::<_, String>((k, expr_to_pat(db, x)?)))
-                .collect::<Result<_, _>>()?,
-        ),
+        Literal::Object(_) => unreachable!("record patterns are converted by expr_to_pat"),
     })
 }
 
@@ -184,26 +458,111 @@ fn normalize_app(f: Expr, x: Expr) -> ExprKind {
     }
 }
 
-type ParseResult<'a, T> = (
-    Vec<ErrorRecovery<usize, Token, &'a str>>,
+type ParseResult<T> = (
+    Vec<ErrorRecovery<usize, Token, Error>>,
     Result<T, ParseError<usize, Token, Error>>,
 );
 
+/// Converts the errors the parser recovered from, followed by the fatal one if parsing failed,
+/// into diagnostics located in `module`.
+pub fn parse_diagnostics<T>(
+    module: crate::ModuleId,
+    (errors, result): &ParseResult<T>,
+) -> Vec<crate::Diagnostic> {
+    use crate::ToDiagnostic;
+    errors
+        .iter()
+        .map(|err| err.to_diagnostic(module))
+        .chain(result.as_ref().err().map(|err| err.to_diagnostic(module)))
+        .collect()
+}
+
+/// Toggles for syntax that differs between PureScript versions. The default accepts the syntax of
+/// the latest version.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct ParseOptions {
+    /// Accept tabs in indentation, see `LexOptions::allow_tabs`
+    pub allow_tabs: bool,
+    /// Treat `∀ ∷ → ⇒ ⇐ ←` as ordinary operators rather than Unicode spellings of `forall`, `::`
+    /// etc., so that code from before they were reserved can define them
+    pub ascii_only_syntax: bool,
+}
+
 pub fn parse_module<'a>(
     db: &'a dyn crate::Db,
     input: &'a str,
     module: crate::ModuleId,
-) -> ParseResult<'a, Module> {
+) -> ParseResult<Module> {
     let mut errors = vec![];
-    let lexer = lexer::lex(input);
-    let result = parser::ModuleParser::new().parse(db, &mut errors, module, lexer);
+    let mut warnings = vec![];
+    let mut comments = vec![];
+    let lexer = lexer::lex_with_comments(input, Default::default(), &mut warnings, &mut comments);
+    let mut result = parser::ModuleParser::new().parse(db, &mut errors, module, lexer);
+    if let Ok(module) = &mut result {
+        attach_comments(input, module, &comments);
+    }
+    (errors, result)
+}
+
+/// Like `parse_module`, for code targeting another PureScript version. Non-fatal lexer problems
+/// (e.g. mixed tabs and spaces) are pushed to `warnings`.
+pub fn parse_module_with_options<'a>(
+    db: &'a dyn crate::Db,
+    input: &'a str,
+    module: crate::ModuleId,
+    options: ParseOptions,
+    warnings: &'a mut Vec<Error>,
+) -> ParseResult<Module> {
+    let mut errors = vec![];
+    let lex_options = lexer::LexOptions {
+        allow_tabs: options.allow_tabs,
+        ascii_only_syntax: options.ascii_only_syntax,
+    };
+    let mut comments = vec![];
+    let lexer = lexer::lex_with_comments(input, lex_options, warnings, &mut comments);
+    let mut result = parser::ModuleParser::new().parse(db, &mut errors, module, lexer);
+    if let Ok(module) = &mut result {
+        attach_comments(input, module, &comments);
+    }
     (errors, result)
 }
 
+/// Attaches comments to the top-level declaration directly following them, and comments before the
+/// module header to the module.
+///
+/// A declaration gets the comments separated from it only by whitespace, starting with one that
+/// begins its line. This leaves out comments inside the previous declaration or after its end on
+/// the same line.
+fn attach_comments(input: &str, module: &mut Module, comments: &[(usize, Comment, usize)]) {
+    let begins_line = |start: usize| {
+        let line_start = input[..start].rfind('\n').map_or(0, |i| i + 1);
+        input[line_start..start].trim().is_empty()
+    };
+    let header_end = comments.partition_point(|(_, _, end)| *end <= module.0.start);
+    module.1 .0 = comments[..header_end]
+        .iter()
+        .map(|(_, comment, _)| comment.clone())
+        .collect();
+    for decl in &mut module.1 .1.declarations {
+        let end = comments.partition_point(|(_, _, end)| *end <= decl.0.start);
+        let mut start = end;
+        let mut next_start = decl.0.start;
+        while start > header_end && input[comments[start - 1].2..next_start].trim().is_empty() {
+            start -= 1;
+            next_start = comments[start].0;
+        }
+        decl.1 .0 = comments[start..end]
+            .iter()
+            .skip_while(|(start, _, _)| !begins_line(*start))
+            .map(|(_, comment, _)| comment.clone())
+            .collect();
+    }
+}
+
 pub fn parse_lower_qualified_ident<'a>(
     db: &'a dyn crate::Db,
     input: &'a str,
-) -> ParseResult<'a, QualifiedName> {
+) -> ParseResult<QualifiedName> {
     let mut errors = vec![];
     let lexer = lexer::lex(input);
     let module = ModuleId::new(db, "Bundle".into());
@@ -233,7 +592,7 @@ pub fn parse_type<'a>(
     db: &'a dyn crate::Db,
     input: &'a str,
     module: crate::ModuleId,
-) -> ParseResult<'a, Type> {
+) -> ParseResult<Type> {
     let mut errors = vec![];
     let lexer = lexer::lex(input);
     let result = parser::TypeParser::new().parse(db, &mut errors, module, lexer);
@@ -244,18 +603,42 @@ pub fn parse_expr<'a>(
     db: &'a dyn crate::Db,
     input: &'a str,
     module: crate::ModuleId,
-) -> ParseResult<'a, Expr> {
+) -> ParseResult<Expr> {
     let mut errors = vec![];
     let lexer = lexer::lex(input);
     let result = parser::ExprParser::new().parse(db, &mut errors, module, lexer);
     (errors, result)
 }
 
+pub fn parse_declaration<'a>(
+    db: &'a dyn crate::Db,
+    input: &'a str,
+    module: crate::ModuleId,
+) -> ParseResult<Declaration> {
+    let mut errors = vec![];
+    let lexer = lexer::lex(input);
+    let result = parser::DeclarationParser::new().parse(db, &mut errors, module, lexer);
+    (errors, result)
+}
+
+pub fn parse_pattern<'a>(
+    db: &'a dyn crate::Db,
+    input: &'a str,
+    module: crate::ModuleId,
+) -> ParseResult<Pat> {
+    let mut errors = vec![];
+    let lexer = lexer::lex(input);
+    let result = parser::PatParser::new().parse(db, &mut errors, module, lexer);
+    (errors, result)
+}
+
 #[cfg(test)]
 mod tests {
+    use crate::ast::{ExprKind, Literal, Located};
     use crate::utils::tests::*;
     use indoc::indoc;
     use insta::{self, assert_debug_snapshot, assert_snapshot};
+    use salsa::DebugWithDb;
 
     fn expect_success<'db, T: salsa::DebugWithDb<<crate::Jar as ::salsa::jar::Jar<'db>>::DynDb>>(
         db: &<crate::Jar as ::salsa::jar::Jar<'db>>::DynDb,
@@ -280,7 +663,24 @@ mod tests {
     fn parse_expr(input: &str) -> String {
         let db = crate::Database::new();
         let module = dummy_module(&db);
-        expect_success(&db, super::parse_expr(&db, input, module))
+        let result = expect_success(&db, super::parse_expr(&db, input, module));
+        // Formatting the expression has to give back the same AST
+        assert_print_reparses(
+            input,
+            |input| super::parse_expr(&db, input, module),
+            |expr| crate::pretty_printer::pp(&db, expr).to_string(),
+        );
+        result
+    }
+    fn parse_declaration(input: &str) -> String {
+        let db = crate::Database::new();
+        let module = dummy_module(&db);
+        expect_success(&db, super::parse_declaration(&db, input, module))
+    }
+    fn parse_pattern(input: &str) -> String {
+        let db = crate::Database::new();
+        let module = dummy_module(&db);
+        expect_success(&db, super::parse_pattern(&db, input, module))
     }
 
     #[test]
@@ -321,6 +721,45 @@ mod tests {
         )));
     }
 
+    #[test]
+    fn test_module_name_with_digits_and_underscores() {
+        assert_snapshot!(parse_module(indoc!(
+            "
+        module A1.B_c where
+        import Data.Foo'
+        "
+        )));
+    }
+
+    #[test]
+    fn test_module_name_lowercase_component() {
+        let db = crate::Database::new();
+        let module = dummy_module(&db);
+        for (input, component) in [
+            ("module foo.Bar where\n", "foo"),
+            ("module Foo.bar where\n", "bar"),
+            ("module Foo where\nimport Foo.bar.Baz\n", "bar"),
+        ] {
+            let (_, result) = super::parse_module(&db, input, module);
+            let start = input.find(component).unwrap();
+            let error = crate::errors::Error::new(
+                start,
+                start + component.len(),
+                crate::errors::ErrorKind::LowercaseModuleNameComponent,
+            );
+            assert_eq!(
+                result,
+                Err(lalrpop_util::ParseError::User {
+                    error: error.clone()
+                })
+            );
+            assert_eq!(
+                format!("{}", error.kind),
+                "module name components must start uppercase"
+            );
+        }
+    }
+
     #[test]
     fn test_simple_value_decl() {
         assert_snapshot!(parse_module(indoc!(
@@ -331,6 +770,106 @@ mod tests {
         )));
     }
 
+    #[test]
+    fn test_guarded_value_decl() {
+        assert_snapshot!(parse_module(indoc!(
+            "
+        module Foo where
+        sign x
+          | x > 0 = 1
+          | x < 0 = -1
+          | otherwise = 0
+        "
+        )));
+    }
+
+    #[test]
+    fn test_ascii_only_syntax_option() {
+        let input = indoc!(
+            "
+        module Foo where
+        infixr 0 apply as →
+        x = f → y
+        "
+        );
+        let db = crate::Database::new();
+        let module = parse_module_id(input, &db);
+        let (errors, result) = super::parse_module(&db, input, module);
+        assert!(!errors.is_empty() || result.is_err());
+
+        let options = super::ParseOptions {
+            ascii_only_syntax: true,
+            ..Default::default()
+        };
+        let mut warnings = vec![];
+        let (errors, result) =
+            super::parse_module_with_options(&db, input, module, options, &mut warnings);
+        assert_eq!(errors, vec![]);
+        assert_eq!(warnings, vec![]);
+        let module = result.unwrap();
+        let crate::ast::DeclarationKind::Operator { operator, .. } = &module.declarations[0].1 .1
+        else {
+            panic!("expected operator declaration")
+        };
+        assert_eq!(operator.text(&db), "→");
+    }
+
+    #[test]
+    fn test_interleaved_guards() {
+        let input = indoc!(
+            "
+        module Foo where
+        f x | x > 0, Just y <- g x, y < 10 = y
+        "
+        );
+        assert_snapshot!(parse_module(input));
+
+        let db = crate::Database::new();
+        let module_id = parse_module_id(input, &db);
+        let (errors, module) = super::parse_module(&db, input, module_id);
+        assert_eq!(errors, vec![]);
+        let module = module.unwrap();
+        let crate::ast::DeclarationKind::ValueDeclaration(value) = &module.declarations[0].1 .1
+        else {
+            panic!("expected value declaration")
+        };
+        let crate::ast::PossiblyGuardedExpr::Guarded(guarded) = &value.expr else {
+            panic!("expected guarded expression")
+        };
+        let guards = guarded[0]
+            .guards
+            .iter()
+            .map(|guard| match guard {
+                crate::ast::Guard::Expr(_) => "expr",
+                crate::ast::Guard::Bind(..) => "bind",
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(guards, vec!["expr", "bind", "expr"]);
+    }
+
+    #[test]
+    fn test_constrained_type_synonym() {
+        assert_snapshot!(parse_module(indoc!(
+            "
+        module Foo where
+        type Showable a = Show a => a
+        "
+        )));
+    }
+
+    #[test]
+    fn test_multiline_string_decl() {
+        assert_snapshot!(parse_module(indoc!(
+            r#"
+        module Foo where
+        x = """
+          Some "quoted" ""text""
+        """
+        y = 1
+        "#
+        )));
+    }
+
     #[test]
     fn test_typed_value_decl() {
         assert_snapshot!(parse_module(indoc!(
@@ -512,6 +1051,30 @@ mod tests {
         )));
     }
 
+    #[test]
+    fn test_fundep_unknown_variable() {
+        let db = crate::Database::new();
+        let input = indoc!(
+            r#"
+            module Test where
+            class Foo a b | a -> c where
+        "#
+        );
+        let module = parse_module_id(input, &db);
+        let (_, result) = super::parse_module(&db, input, module);
+        let start = input.find("c where").unwrap();
+        assert_eq!(
+            result,
+            Err(lalrpop_util::ParseError::User {
+                error: crate::errors::Error::new(
+                    start,
+                    start + 1,
+                    crate::errors::ErrorKind::UnknownFundepVariable("c".into())
+                )
+            })
+        );
+    }
+
     #[test]
     fn test_kind_signature_class() {
         assert_snapshot!(parse_module(indoc!(
@@ -607,6 +1170,38 @@ mod tests {
         )));
     }
 
+    #[test]
+    fn test_instance_nested_app() {
+        assert_snapshot!(parse_module(indoc!(
+            r#"
+            module Test where
+            instance Foo (Maybe a) (Array b)
+        "#
+        )));
+    }
+
+    #[test]
+    fn test_instance_head_nested_app_args() {
+        let db = crate::Database::new();
+        let module = dummy_module(&db);
+        let ty = super::parse_type(&db, "Foo (Maybe a) (Array b)", module)
+            .1
+            .unwrap();
+        let (class, args) = super::constraint_to_instance_head(ty).unwrap();
+        assert_eq!(class.name(&db).text(&db), "Foo");
+        let arg_heads = args
+            .into_iter()
+            .map(|arg| {
+                let (con, arg_args) = super::constraint_to_instance_head(arg).unwrap();
+                (con.name(&db).text(&db).clone(), arg_args.len())
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            arg_heads,
+            vec![("Maybe".to_string(), 1), ("Array".to_string(), 1)]
+        );
+    }
+
     #[test]
     fn test_instance_deriving() {
         assert_snapshot!(parse_module(indoc!(
@@ -618,6 +1213,49 @@ mod tests {
         )));
     }
 
+    #[test]
+    fn test_newtype_instance_is_not_supported() {
+        // Newtype deriving is spelled `derive newtype instance` in PureScript; there is no
+        // `newtype instance` form
+        let input = "module Test where\nnewtype instance Foo Int\n";
+        let db = crate::Database::new();
+        let module = parse_module_id(input, &db);
+        let (errors, result) = super::parse_module(&db, input, module);
+        assert!(!errors.is_empty() || result.is_err());
+    }
+
+    #[test]
+    fn test_instance_qualified_class() {
+        assert_snapshot!(parse_module(indoc!(
+            r#"
+            module Test where
+            instance Data.Show.Show a => Data.Show.Show (Maybe a) where
+              show _ = ""
+        "#
+        )));
+    }
+
+    #[test]
+    fn test_qualified_class_declaration_is_rejected() {
+        let db = crate::Database::new();
+        let input = "module Test where\nclass Data.Eq.Eq a\n";
+        let module = parse_module_id(input, &db);
+        let (_, result) = super::parse_module(&db, input, module);
+        assert!(
+            matches!(
+                result,
+                Err(lalrpop_util::ParseError::User {
+                    error: crate::errors::Error {
+                        kind: crate::errors::ErrorKind::InvalidClassHead,
+                        ..
+                    }
+                })
+            ),
+            "{:?}",
+            result
+        );
+    }
+
     #[test]
     fn test_instance_chain() {
         assert_snapshot!(parse_module(indoc!(
@@ -642,6 +1280,21 @@ mod tests {
         )));
     }
 
+    #[test]
+    fn test_instance_chain_3() {
+        assert_snapshot!(parse_module(indoc!(
+            r#"
+            module Test where
+            instance Foo Int where
+              x = 1
+            else instance Foo String where
+              x = 2
+            else instance Foo a where
+              x = 3
+        "#
+        )));
+    }
+
     #[test]
     fn test_instance_method_sig() {
         assert_snapshot!(parse_module(indoc!(
@@ -653,6 +1306,31 @@ mod tests {
         )));
     }
 
+    #[test]
+    fn test_instance_method_operator() {
+        assert_snapshot!(parse_module(indoc!(
+            r#"
+            module Test where
+            instance Semigroup Int where
+                (<>) x y = intAdd x y
+        "#
+        )));
+    }
+
+    #[test]
+    fn test_operator_definition_outside_instance() {
+        let db = crate::Database::new();
+        for input in [
+            "module Test where\n(+~) x y = x\n",
+            "module Test where\nf = z where\n  (+~) x y = x\n",
+            "module Test where\nf = let (+~) x y = x in z\n",
+        ] {
+            let module = parse_module_id(input, &db);
+            let (errors, result) = super::parse_module(&db, input, module);
+            assert!(!errors.is_empty() || result.is_err(), "{input:?} parsed");
+        }
+    }
+
     #[test]
     #[ignore = "Not implemented yet"]
     fn test_instance_method_infix() {
@@ -678,6 +1356,60 @@ mod tests {
         )));
     }
 
+    #[test]
+    fn test_standalone_declaration() {
+        assert_snapshot!(parse_declaration(indoc!(
+            r#"
+            instance Foo Int where
+              foo x = 1
+              bar = 2
+        "#
+        )));
+    }
+
+    #[test]
+    fn test_standalone_declaration_matches_module_declaration() {
+        use crate::ast::normalize::StripSpans;
+
+        let db = &crate::Database::new();
+        let module = dummy_module(db);
+        for input in [
+            "f x y = 1",
+            "f :: forall a. a -> Int",
+            "type Baz a b = a",
+            "type Foo :: Type -> Type",
+            "foreign import foo :: Int -> Int",
+            "foreign import data X :: Type",
+            "data Maybe a = Nothing | Just a",
+            "newtype Foo = Foo Int",
+            "class Bar a <= Foo a where\n  foo :: a -> Bool\n  bar :: a",
+            "instance Bar a => Foo a where\n  bar = 1",
+            "infixl 6 add as +",
+        ] {
+            let (errors, decl) = super::parse_declaration(db, input, module);
+            assert_eq!(errors, vec![], "{}", input);
+            let mut decl = decl.unwrap();
+            decl.strip_spans();
+
+            let module_input = format!("module Test where\n{}\n", input);
+            let (errors, parsed_module) = super::parse_module(db, &module_input, module);
+            assert_eq!(errors, vec![], "{}", input);
+            let mut expected = parsed_module.unwrap().normalize().1 .1.declarations;
+            assert_eq!(expected.len(), 1, "{}", input);
+            assert_eq!(decl, expected.remove(0), "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_operator_data_constructor() {
+        assert_snapshot!(parse_module(indoc!(
+            r#"
+            module Test where
+            data NonEmpty a = (:|) a (Array a)
+        "#
+        )));
+    }
+
     #[test]
     fn test_parse_atomic_type() {
         assert_snapshot!(parse_type("var"));
@@ -688,6 +1420,17 @@ mod tests {
         assert_snapshot!(parse_type("(-42)"));
     }
 
+    #[test]
+    fn test_parse_type_wildcards_and_holes() {
+        assert_snapshot!(parse_type("_ -> _"));
+        assert_snapshot!(parse_type("Maybe ?a"));
+    }
+
+    #[test]
+    fn test_kinded_forall_binder() {
+        assert_snapshot!(parse_type("forall (f :: Type -> Type) a. f a"));
+    }
+
     #[test]
     fn test_parse_complex_type() {
         assert_snapshot!(parse_type("Maybe Int"));
@@ -700,6 +1443,27 @@ mod tests {
         assert_snapshot!(parse_type("forall x (y :: Symbol). Maybe x"));
     }
 
+    #[test]
+    fn test_parse_unicode_forall() {
+        use crate::ast::normalize::StripSpans;
+
+        let db = crate::Database::new();
+        let module = dummy_module(&db);
+        let parse = |input| {
+            let (errors, result) = super::parse_type(&db, input, module);
+            assert_eq!(errors, vec![]);
+            let type_ = result.unwrap();
+            // `∀` is three bytes long
+            assert_eq!((type_.0.start, type_.0.end), (0, input.len()));
+            type_
+        };
+        let mut unicode = parse("∀ a. a");
+        let mut ascii = parse("forall a. a");
+        unicode.strip_spans();
+        ascii.strip_spans();
+        assert_eq!(unicode, ascii);
+    }
+
     #[test]
     fn test_parse_constraint() {
         assert_snapshot!(parse_type("Eq a => a"));
@@ -711,33 +1475,251 @@ mod tests {
     }
 
     #[test]
-    fn test_parse_row_1() {
-        assert_snapshot!(parse_type("( foo :: Int, \"Bar\" :: String, data :: Int )"));
+    fn test_empty_constraint_context() {
+        use crate::ast::normalize::StripSpans;
+
+        assert_snapshot!(parse_type("() => Int"));
+
+        let db = crate::Database::new();
+        let module = dummy_module(&db);
+        let mut with_context = super::parse_type(&db, "() => Int", module).1.unwrap();
+        let mut without_context = super::parse_type(&db, "Int", module).1.unwrap();
+        with_context.strip_spans();
+        without_context.strip_spans();
+        assert_eq!(with_context, without_context);
     }
 
     #[test]
-    fn test_parse_row_2() {
-        assert_snapshot!(parse_type("( foo :: Int | e )"));
+    fn test_forall_after_constraint() {
+        let db = crate::Database::new();
+        let module = dummy_module(&db);
+        let input = "Eq a => forall b. a -> b";
+        let (_, result) = super::parse_type(&db, input, module);
+        assert_eq!(
+            result,
+            Err(lalrpop_util::ParseError::User {
+                error: crate::errors::Error::new(
+                    input.find("forall").unwrap(),
+                    input.len(),
+                    crate::errors::ErrorKind::UnexpectedForallAfterConstraint
+                )
+            })
+        );
     }
 
     #[test]
-    fn test_parse_row_3() {
-        assert_snapshot!(parse_type("( | e )"));
+    fn test_let_semicolons() {
+        assert_snapshot!(parse_expr("let x = 1; y = 2 in x + y"));
     }
 
     #[test]
-    fn test_parse_row_4() {
-        assert_snapshot!(parse_type("()"));
+    fn test_where_semicolons() {
+        assert_snapshot!(parse_module(indoc!(
+            "
+            module Test where
+            f = x + y
+              where x = 1; y = 2
+            g = do
+              a <- x; b <- y
+              pure b
+            "
+        )));
     }
 
     #[test]
-    fn test_parse_record() {
-        assert_snapshot!(parse_type("{ foo :: Int | e }"));
+    fn test_explicit_brace_do() {
+        assert_snapshot!(parse_expr("do { x <- f; pure x }"));
     }
 
     #[test]
-    fn test_parse_function_type() {
-        assert_snapshot!(parse_type("A -> B -> C"));
+    fn test_qualified_do() {
+        assert_snapshot!(parse_expr("M.do { x <- f; pure x }"));
+    }
+
+    #[test]
+    fn test_qualified_ado() {
+        assert_snapshot!(parse_expr(indoc!(
+            "
+            Control.Apply.ado
+              x <- f
+              in x
+            "
+        )));
+    }
+
+    #[test]
+    fn test_explicit_brace_let() {
+        assert_snapshot!(parse_expr("let { x = 1; y = 2 } in x + y"));
+    }
+
+    #[test]
+    fn test_explicit_brace_where_and_case() {
+        assert_snapshot!(parse_module(indoc!(
+            "
+            module Test where
+            f x = g x
+              where {
+                g y = case y of { Just z -> z; Nothing -> 0 }
+              }
+            "
+        )));
+    }
+
+    #[test]
+    fn test_record_pattern_starting_block() {
+        assert_snapshot!(parse_expr(indoc!(
+            "
+            case r of
+              { x: y } -> y
+            "
+        )));
+        assert_snapshot!(parse_expr(indoc!(
+            "
+            case r of
+              { x } -> x
+            "
+        )));
+        assert_snapshot!(parse_expr(indoc!(
+            "
+            do
+              { a: b } <- foo
+              pure b
+            "
+        )));
+        assert_snapshot!(parse_expr(indoc!(
+            "
+            let
+              { a: b } = r
+            in b
+            "
+        )));
+        assert_snapshot!(parse_module(indoc!(
+            "
+            module Test where
+            f = x
+              where
+                { a: x } = r
+            "
+        )));
+    }
+
+    #[test]
+    fn test_record_pattern_after_layout_keyword() {
+        assert_snapshot!(parse_expr(indoc!(
+            "
+            do
+              let { x, y } = point
+              pure (x + y)
+            "
+        )));
+        assert_snapshot!(parse_expr("case r of { x } -> x"));
+        assert_snapshot!(parse_expr("let { a: b } = r in b"));
+    }
+
+    #[test]
+    fn test_instance_in_where() {
+        let db = crate::Database::new();
+        let input = indoc!(
+            "
+            module Test where
+            foo = x
+              where
+                instance Show Foo where
+                  show _ = x
+                x = 1
+            "
+        );
+        let module = parse_module_id(input, &db);
+        let (_, result) = super::parse_module(&db, input, module);
+        let start = input.find("instance").unwrap();
+        let end = input.find("x = 1").unwrap();
+        assert_eq!(
+            result,
+            Err(lalrpop_util::ParseError::User {
+                error: crate::errors::Error::new(
+                    start,
+                    start + input[start..end].trim_end().len(),
+                    crate::errors::ErrorKind::InstanceNotAtTopLevel
+                )
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_row_1() {
+        assert_snapshot!(parse_type("( foo :: Int, \"Bar\" :: String, data :: Int )"));
+    }
+
+    #[test]
+    fn test_parse_row_2() {
+        assert_snapshot!(parse_type("( foo :: Int | e )"));
+    }
+
+    #[test]
+    fn test_parse_row_3() {
+        assert_snapshot!(parse_type("( | e )"));
+    }
+
+    #[test]
+    fn test_parse_row_4() {
+        assert_snapshot!(parse_type("()"));
+    }
+
+    #[test]
+    fn test_parse_record() {
+        assert_snapshot!(parse_type("{ foo :: Int | e }"));
+    }
+
+    #[test]
+    fn test_parse_record_quoted_label() {
+        assert_snapshot!(parse_type(r#"{ "a b" :: Int, foo :: String | e }"#));
+    }
+
+    #[test]
+    fn test_parse_large_record() {
+        // Fields are accumulated by pushing onto a `Vec`, so this should take time linear in the
+        // number of fields
+        let fields = (0..5000)
+            .map(|i| format!("f{} :: Int", i))
+            .collect::<Vec<_>>();
+        let input = format!("{{ {} }}", fields.join(", "));
+        let db = crate::Database::new();
+        let module = dummy_module(&db);
+        let (errors, result) = super::parse_type(&db, &input, module);
+        assert_eq!(errors, vec![]);
+        match result.unwrap().1 {
+            super::TypeKind::TypeApp(_, row) => match row.1 {
+                super::TypeKind::Row { fields, .. } => assert_eq!(fields.len(), 5000),
+                _ => panic!("expected a row"),
+            },
+            _ => panic!("expected a record type"),
+        }
+    }
+
+    #[test]
+    fn test_record_and_row_quoted_labels() {
+        let db = crate::Database::new();
+        let module = dummy_module(&db);
+        let row_labels = |ty: &super::Type| match &ty.1 {
+            super::TypeKind::Row { fields, .. } => {
+                fields.iter().map(|(label, _)| *label).collect::<Vec<_>>()
+            }
+            _ => panic!("expected a row"),
+        };
+        let record = super::parse_type(&db, r#"{ "foo" :: Int }"#, module)
+            .1
+            .unwrap();
+        let record_labels = match &record.1 {
+            super::TypeKind::TypeApp(_, row) => row_labels(row),
+            _ => panic!("expected a record type"),
+        };
+        let row = super::parse_type(&db, "( foo :: Int )", module).1.unwrap();
+        assert_eq!(record_labels, row_labels(&row));
+    }
+
+    #[test]
+    fn test_parse_function_type() {
+        assert_snapshot!(parse_type("A -> B -> C"));
     }
 
     #[test]
@@ -778,6 +1760,23 @@ mod tests {
         assert_snapshot!(parse_expr("12.34"));
     }
 
+    #[test]
+    fn test_integer_and_float_literals_distinct() {
+        let db = crate::Database::new();
+        let module = dummy_module(&db);
+        let literal = |input| match super::parse_expr(&db, input, module).1.unwrap().1 {
+            ExprKind::Literal(lit) => lit,
+            other => panic!("expected a literal, got {:?}", other),
+        };
+        assert_eq!(literal("1"), Literal::Integer(1));
+        assert_eq!(literal("1.0"), Literal::Float(1.0.into()));
+    }
+
+    #[test]
+    fn test_float_literal_exponent() {
+        assert_snapshot!(parse_expr("6.022e23"));
+    }
+
     #[test]
     fn test_float_literal_with_underscores() {
         assert_snapshot!(parse_expr("15_000.0"));
@@ -828,11 +1827,42 @@ mod tests {
         assert_snapshot!(parse_expr(r#" foo.bar.baz "#));
     }
 
+    #[test]
+    fn test_type_application() {
+        assert_snapshot!(parse_expr(r#"read @Int "5""#));
+    }
+
+    #[test]
+    fn test_type_application_chained() {
+        assert_snapshot!(parse_expr("f x @A @(B c) y"));
+    }
+
+    #[test]
+    fn test_kind_application() {
+        assert_snapshot!(parse_expr("Proxy :: Proxy @Symbol"));
+    }
+
+    #[test]
+    fn test_hole() {
+        assert_snapshot!(parse_expr("f ?x"));
+    }
+
+    #[test]
+    fn test_hole_in_infix_chain() {
+        assert_snapshot!(parse_expr("a + ?_ * b"));
+    }
+
     #[test]
     fn test_parse_qualified_var() {
         assert_snapshot!(parse_expr(r#"Data.Maybe.fromJust"#));
     }
 
+    #[test]
+    fn test_accessor_on_qualified_var() {
+        // The lexer stops the qualified name at the first lowercase segment
+        assert_snapshot!(parse_expr("Config.defaults.timeout"));
+    }
+
     #[test]
     fn test_parse_parens() {
         assert_snapshot!(parse_expr(r#"(foo)"#));
@@ -882,6 +1912,27 @@ mod tests {
         )));
     }
 
+    #[test]
+    fn test_constructor_app_in_infix_pattern() {
+        assert_snapshot!(parse_expr(indoc!(
+            "
+          case xs of
+            Just x : rest -> rest
+        "
+        )));
+    }
+
+    #[test]
+    fn test_constructor_pattern_type_args() {
+        assert_snapshot!(parse_expr(indoc!(
+            "
+          case x of
+            Just @Int y -> y
+            Proxy @(Array a) -> 0
+        "
+        )));
+    }
+
     #[test]
     fn test_case_guards_1() {
         assert_snapshot!(parse_expr(indoc!(
@@ -903,6 +1954,19 @@ mod tests {
         )));
     }
 
+    #[test]
+    fn test_case_guards_3() {
+        assert_snapshot!(parse_expr(indoc!(
+            "
+          case x of
+            n | n > 0 -> 1
+              | Just y <- f n, y > 1 -> y
+              | otherwise -> 0
+            _ -> 2
+        "
+        )));
+    }
+
     #[test]
     fn test_case_multi() {
         assert_snapshot!(parse_expr(indoc!(
@@ -913,6 +1977,33 @@ mod tests {
         )));
     }
 
+    #[test]
+    fn test_case_arity_mismatch() {
+        let db = crate::Database::new();
+        let module = dummy_module(&db);
+        let input = indoc!(
+            "
+          case x, y of
+            C, D -> 1
+            E -> 2
+        "
+        );
+        let (_, result) = super::parse_expr(&db, input, module);
+        assert_eq!(
+            result,
+            Err(lalrpop_util::ParseError::User {
+                error: crate::errors::Error::new(
+                    input.find('E').unwrap(),
+                    input.find('E').unwrap() + 1,
+                    crate::errors::ErrorKind::CaseArityMismatch {
+                        scrutinees: 2,
+                        patterns: 1
+                    }
+                )
+            })
+        );
+    }
+
     #[test]
     fn test_typed_expr() {
         assert_snapshot!(parse_expr("foo bar :: Int"));
@@ -998,6 +2089,77 @@ mod tests {
         assert_snapshot!(parse_expr("\\x -> 1 :: Int"));
     }
 
+    #[test]
+    fn test_standalone_patterns() {
+        assert_snapshot!(parse_pattern(indoc!(
+            r#"
+            Just (Tuple xs@[_, 42] { x, y: "foo" }) :: Maybe T
+            "#
+        )));
+    }
+
+    #[test]
+    fn test_case_empty_array_pattern() {
+        assert_snapshot!(parse_expr(indoc!(
+            "
+            case xs of
+              [] -> 0
+              _ -> 1
+            "
+        )));
+    }
+
+    #[test]
+    fn test_case_empty_record_pattern() {
+        assert_snapshot!(parse_expr(indoc!(
+            "
+            case r of
+              {} -> 0
+            "
+        )));
+    }
+
+    #[test]
+    fn test_record_patterns() {
+        assert_snapshot!(parse_pattern("{ x, y: Just z }"));
+    }
+
+    #[test]
+    fn test_standalone_pattern_matches_param_pattern() {
+        let db = &crate::Database::new();
+        let module = dummy_module(db);
+        for input in [
+            "x",
+            "_",
+            "[x, y]",
+            "{x, y: 1}",
+            "{ x }",
+            "{ x: Just y }",
+            "[]",
+            "{}",
+            "{ x, y: Just z, type: t }",
+            r#""foo""#,
+            "42",
+            "(x)",
+            "Nothing",
+            "Data.Maybe.Just x",
+            "x@(Just _)",
+            "Cons (Just x) rest",
+            "x : y : rest",
+            "(x :: Int)",
+            "Proxy @Int",
+            "Just @Int x",
+            "Tuple @(Array Int) @String a b",
+        ] {
+            let (errors, pat) = super::parse_pattern(db, input, module);
+            assert_eq!(errors, vec![], "{}", input);
+            let (errors, expr) = super::parse_expr(db, input, module);
+            assert_eq!(errors, vec![], "{}", input);
+            let expected = super::expr_to_pat(db, expr.unwrap()).unwrap();
+            assert_eq!(pat.unwrap(), expected, "{}", input);
+        }
+    }
+
     #[test]
     fn test_named_pattern() {
         assert_snapshot!(parse_expr("\\x@Nothing -> y"));
@@ -1028,6 +2190,59 @@ mod tests {
         assert_snapshot!(parse_expr("f r { x = 1 } { y: 2 } q"));
     }
 
+    #[test]
+    fn test_record_update_nested() {
+        assert_snapshot!(parse_expr("r { a { b { c = 1 } }, d = 2 }"));
+    }
+
+    #[test]
+    fn test_record_update_pun_is_rejected() {
+        let db = crate::Database::new();
+        let module = dummy_module(&db);
+        let input = "r { x, y = 1 }";
+        let (_, result) = super::parse_expr(&db, input, module);
+        assert_eq!(
+            result,
+            Err(lalrpop_util::ParseError::User {
+                error: crate::errors::Error::new(
+                    input.find('x').unwrap(),
+                    input.find('x').unwrap() + 1,
+                    crate::errors::ErrorKind::PunInRecordUpdate
+                )
+            })
+        );
+    }
+
+    #[test]
+    fn test_record_update_literal_field_is_rejected() {
+        let db = crate::Database::new();
+        let module = dummy_module(&db);
+        let input = "r { x = 1, y: 2 }";
+        let (_, result) = super::parse_expr(&db, input, module);
+        assert_eq!(
+            result,
+            Err(lalrpop_util::ParseError::User {
+                error: crate::errors::Error::new(
+                    input.find('2').unwrap(),
+                    input.find('2').unwrap() + 1,
+                    crate::errors::ErrorKind::LiteralFieldInRecordUpdate
+                )
+            })
+        );
+    }
+
+    #[test]
+    fn test_record_literal_pun_argument() {
+        assert_snapshot!(parse_expr("{ x }"));
+        assert_snapshot!(parse_expr("Just { x, y: 1 }"));
+    }
+
+    #[test]
+    fn test_record_literal_pun_argument_of_function() {
+        assert_snapshot!(parse_expr("pure { x, y }"));
+        assert_snapshot!(parse_expr("f a { x }"));
+    }
+
     #[test]
     fn test_do_simple() {
         assert_snapshot!(parse_expr(indoc!(
@@ -1061,6 +2276,30 @@ mod tests {
         )));
     }
 
+    #[test]
+    fn test_do_typed_expression_statement() {
+        assert_snapshot!(parse_expr(indoc!(
+            "
+          do
+            log \"start\"
+            (pure 1 :: Effect Int)
+        "
+        )));
+    }
+
+    #[test]
+    fn test_do_let_with_signature() {
+        assert_snapshot!(parse_expr(indoc!(
+            "
+          do
+            let
+              x :: Int
+              x = 1
+            pure x
+        "
+        )));
+    }
+
     #[test]
     fn test_do_bind_type_sig() {
         assert_snapshot!(parse_expr(indoc!(
@@ -1107,6 +2346,22 @@ mod tests {
         )));
     }
 
+    #[test]
+    fn test_ado_trailing_in() {
+        assert_snapshot!(parse_expr(indoc!(
+            "
+          ado
+            x <- f
+            y <- g x in Tuple x y
+        "
+        )));
+    }
+
+    #[test]
+    fn test_ado_empty() {
+        assert_snapshot!(parse_expr("ado in 1"));
+    }
+
     #[test]
     fn test_let_where() {
         assert_snapshot!(parse_expr(indoc!(
@@ -1118,6 +2373,17 @@ mod tests {
         )));
     }
 
+    #[test]
+    fn test_let_destructuring_where() {
+        assert_snapshot!(parse_expr(indoc!(
+            "
+        let Tuple a b = t
+              where t = f 5
+        in a
+        "
+        )));
+    }
+
     #[test]
     fn test_case_where() {
         assert_snapshot!(parse_expr(indoc!(
@@ -1215,10 +2481,36 @@ mod tests {
         infixl 2 g as $%#
         infixr 3 h as <@#%
         infix 1 Foo as ^%
+        infixr 10 Cons as :
+        infixl 0 add as +
         "
         )));
     }
 
+    #[test]
+    fn test_operator_decl_precedence_out_of_range() {
+        let db = crate::Database::new();
+        let input = indoc!(
+            "
+            module Test where
+            infixl 11 add as +
+            "
+        );
+        let module = parse_module_id(input, &db);
+        let (_, result) = super::parse_module(&db, input, module);
+        let start = input.find("11").unwrap();
+        assert_eq!(
+            result,
+            Err(lalrpop_util::ParseError::User {
+                error: crate::errors::Error::new(
+                    start,
+                    start + 2,
+                    crate::errors::ErrorKind::InvalidPrecedence
+                )
+            })
+        );
+    }
+
     #[test]
     fn test_type_operator_decl() {
         assert_snapshot!(parse_module(indoc!(
@@ -1256,11 +2548,241 @@ mod tests {
         assert_snapshot!(parse_expr("case x of -1 -> 1"));
     }
 
+    #[test]
+    fn test_neg_float_pattern() {
+        assert_snapshot!(parse_expr("case x of -2.5 -> 1"));
+    }
+
+    #[test]
+    fn test_neg_pattern_in_function_params() {
+        assert_snapshot!(parse_module(indoc!(
+            "
+            module Test where
+            f (-1) = 0
+            f (-2.5) = 1
+            "
+        )));
+    }
+
+    #[test]
+    fn test_neg_pattern_in_lambda() {
+        assert_snapshot!(parse_expr("\\(-1) -> 0"));
+    }
+
+    #[test]
+    fn test_negated_variable_pattern_is_rejected() {
+        let db = crate::Database::new();
+        let module = dummy_module(&db);
+        let input = "case y of -(x) -> 1";
+        let (_, result) = super::parse_expr(&db, input, module);
+        assert_eq!(
+            result,
+            Err(lalrpop_util::ParseError::User {
+                error: crate::errors::Error::new(
+                    input.find('-').unwrap(),
+                    input.find(')').unwrap() + 1,
+                    crate::errors::ErrorKind::Unknown("Illegal negation in pattern".into())
+                )
+            })
+        );
+    }
+
+    #[test]
+    fn test_section_left() {
+        assert_snapshot!(parse_expr("(_ + 1)"));
+    }
+
+    #[test]
+    fn test_section_right() {
+        assert_snapshot!(parse_expr("(1 + _)"));
+    }
+
+    #[test]
+    fn test_section_backtick() {
+        assert_snapshot!(parse_expr("(_ `mod` 2)"));
+    }
+
+    fn desugar_section(input: &str) -> String {
+        let db = crate::Database::new();
+        let module = dummy_module(&db);
+        let section = match super::parse_expr(&db, input, module).1.unwrap() {
+            Located(_, ExprKind::Section(section)) => *section,
+            other => panic!("expected a section, got {:?}", other),
+        };
+        let desugared = super::desugar_section(&db, section);
+        format!("{:#?}", desugared.into_debug_all(&db)).drop_salsa_id()
+    }
+
+    #[test]
+    fn test_desugar_section() {
+        assert_snapshot!(desugar_section("(_ `mod` _)"));
+    }
+
+    #[test]
+    fn test_desugar_section_backtick() {
+        assert_snapshot!(desugar_section("(_ `div` 2)"));
+    }
+
+    #[test]
+    fn test_desugar_section_application() {
+        assert_snapshot!(desugar_section("(map _ _)"));
+    }
+
+    #[test]
+    fn test_desugar_case_section() {
+        let input = indoc!(
+            "
+            case _ of
+              Just x -> x
+              _ -> 0
+            "
+        );
+        assert_snapshot!(desugar_section(input));
+    }
+
+    #[test]
+    fn test_desugar_multi_case_section() {
+        let db = crate::Database::new();
+        let module = dummy_module(&db);
+        let input = indoc!(
+            "
+            case _, y, _ of
+              a, b, c -> a
+            "
+        );
+        let section = match super::parse_expr(&db, input, module).1.unwrap() {
+            Located(_, ExprKind::Section(section)) => *section,
+            other => panic!("expected a section, got {:?}", other),
+        };
+        match super::desugar_section(&db, section) {
+            ExprKind::Lam(params, body) => {
+                assert_eq!(params.len(), 2);
+                assert!(matches!(body.1, ExprKind::Case { ref exprs, .. } if exprs.len() == 3));
+            }
+            other => panic!("expected a lambda, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_section_in_application_argument() {
+        assert_snapshot!(parse_expr("f (_ + _)"));
+    }
+
+    #[test]
+    fn test_wildcard_in_infix_pattern() {
+        assert_snapshot!(parse_expr("case x of (_ : xs) -> xs"));
+    }
+
     #[test]
     fn test_range_operator() {
         // Should not be confused with float literal and a dot
         assert_snapshot!(parse_expr("1..5"));
     }
 
+    #[test]
+    fn test_adjacent_string_literals_are_application() {
+        // Not concatenated: a string applied to a string, rejected later by the typechecker
+        assert_snapshot!(parse_expr(r#""a" "b""#));
+    }
+
+    #[test]
+    fn test_recover_from_malformed_declaration() {
+        let input = indoc!(
+            "
+            module Test where
+            x = 1
+            y = )
+            z = 3
+            "
+        );
+        assert_eq!(recovered_declarations(input), vec!["x", "z"]);
+    }
+
+    #[test]
+    fn test_recover_from_malformed_block_item() {
+        let input = indoc!(
+            "
+            module Test where
+            x = do
+              a <- )
+              b
+            z = 3
+            "
+        );
+        assert_eq!(recovered_declarations(input), vec!["x", "z"]);
+        let input = indoc!(
+            "
+            module Test where
+            x = case a of
+              ) -> 1
+              _ -> 2
+            z = 3
+            "
+        );
+        assert_eq!(recovered_declarations(input), vec!["x", "z"]);
+    }
+
+    /// Parses a module expecting exactly one syntax error, and returns the names of the value
+    /// declarations that survived it.
+    fn recovered_declarations(input: &str) -> Vec<String> {
+        let db = &crate::Database::new();
+        let module = dummy_module(db);
+        let (errors, result) = super::parse_module(db, input, module);
+        assert_eq!(errors.len(), 1, "{:?}", errors);
+        result
+            .unwrap()
+            .declarations
+            .iter()
+            .map(|decl| match &decl.1 .1 {
+                crate::ast::DeclarationKind::ValueDeclaration(value) => {
+                    value.ident.text(db).clone()
+                }
+                kind => panic!("unexpected declaration {:?}", kind),
+            })
+            .collect()
+    }
+
+    fn diagnostics(input: &str) -> Vec<(usize, usize, String)> {
+        let db = &crate::Database::new();
+        let module = dummy_module(db);
+        let result = super::parse_module(db, input, module);
+        super::parse_diagnostics(module, &result)
+            .into_iter()
+            .map(|diagnostic| {
+                assert_eq!(diagnostic.severity, crate::Severity::Error);
+                (
+                    diagnostic.span.start,
+                    diagnostic.span.end,
+                    diagnostic.message,
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_diagnostic_missing_where() {
+        let input = "module Test\nx = 1\n";
+        let errors = diagnostics(input);
+        assert_eq!(errors.len(), 1, "{:?}", errors);
+        let (start, end, message) = &errors[0];
+        assert_eq!(&input[*start..*end], "x");
+        assert!(message.starts_with("unexpected token"), "{}", message);
+        assert!(message.contains("\"where\""), "{}", message);
+    }
+
+    #[test]
+    fn test_diagnostic_unexpected_token() {
+        let input = "module Test where\nx = )\n";
+        let errors = diagnostics(input);
+        assert_eq!(errors.len(), 1, "{:?}", errors);
+        let (start, end, message) = &errors[0];
+        assert_eq!(&input[*start..*end], ")");
+        assert!(
+            message.starts_with("unexpected token RightParen, expected one of"),
+            "{}",
+            message
+        );
+    }
+
     //
 }
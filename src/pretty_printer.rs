@@ -2,7 +2,9 @@ use crate::ast::Expr;
 use crate::ast::Literal;
 use crate::ast::Pat;
 use crate::ast::Type;
-use pretty::{BoxAllocator, DocAllocator, DocBuilder};
+use crate::source_span::{SourceSpan, SpanDeclRef};
+use pretty::{BoxAllocator, DocAllocator, DocBuilder, Render, RenderAnnotated};
+use std::ops::Range;
 
 use crate::ast::Located;
 use crate::ast::{ExprKind, QualifiedName};
@@ -11,6 +13,9 @@ pub type Precedence = usize;
 
 pub const APP_PRECEDENCE: Precedence = 10;
 pub const FUNCTION_TYPE_PRECEDENCE: Precedence = 0;
+/// Operands of type operators are applications, and a sequence of type operators can itself be
+/// the argument of a function type or a constraint.
+pub const TYPE_INFIX_PRECEDENCE: Precedence = FUNCTION_TYPE_PRECEDENCE + 1;
 
 pub struct PrettyPrintFmt<'a, D, A = ()>(pub DocBuilder<'a, D, A>)
 where
@@ -35,7 +40,7 @@ pub trait PrettyPrint {
     where
         D: DocAllocator<'b, A>,
         D::Doc: Clone,
-        A: Clone;
+        A: SpanAnnotation;
 }
 
 impl<'a, D: DocAllocator<'a, A>, A> std::fmt::Display for PrettyPrintFmt<'a, D, A> {
@@ -44,6 +49,40 @@ impl<'a, D: DocAllocator<'a, A>, A> std::fmt::Display for PrettyPrintFmt<'a, D,
     }
 }
 
+/// Annotation attached to printed AST nodes. Plain printing uses `()`, which drops the spans;
+/// printing with a source map uses the `SourceSpan`s themselves.
+pub trait SpanAnnotation: Clone {
+    fn from_span(span: SourceSpan) -> Option<Self>;
+}
+
+impl SpanAnnotation for () {
+    fn from_span(_: SourceSpan) -> Option<Self> {
+        None
+    }
+}
+
+impl SpanAnnotation for SourceSpan {
+    fn from_span(span: SourceSpan) -> Option<Self> {
+        Some(span)
+    }
+}
+
+fn annotate_span<'b, D, A>(
+    span: SourceSpan,
+    pp_fmt: PrettyPrintFmt<'b, D, A>,
+) -> PrettyPrintFmt<'b, D, A>
+where
+    D: DocAllocator<'b, A>,
+    A: SpanAnnotation,
+{
+    match A::from_span(span) {
+        Some(annotation) if span.decl != SpanDeclRef::Unknown => {
+            PrettyPrintFmt(pp_fmt.0.annotate(annotation))
+        }
+        _ => pp_fmt,
+    }
+}
+
 pub fn parens_when<'b, D, A>(
     allocator: &'b D,
     cond: bool,
@@ -71,7 +110,7 @@ where
     where
         D: DocAllocator<'b, A>,
         D::Doc: Clone,
-        A: Clone,
+        A: SpanAnnotation,
     {
         (**self).pretty_print(db, allocator)
     }
@@ -89,7 +128,7 @@ where
     where
         D: DocAllocator<'b, A>,
         D::Doc: Clone,
-        A: Clone,
+        A: SpanAnnotation,
     {
         (**self).pretty_print(db, allocator)
     }
@@ -104,7 +143,7 @@ impl PrettyPrint for Expr {
     where
         D: DocAllocator<'b, A>,
         D::Doc: Clone,
-        A: Clone,
+        A: SpanAnnotation,
     {
         pretty_print_expr(self, db, allocator, 0)
     }
@@ -119,7 +158,21 @@ fn pretty_print_expr<'b, D, A>(
 where
     D: DocAllocator<'b, A>,
     D::Doc: Clone,
-    A: Clone,
+    A: SpanAnnotation,
+{
+    annotate_span(e.0, pretty_print_expr_kind(e, db, allocator, p))
+}
+
+fn pretty_print_expr_kind<'b, D, A>(
+    e: &Expr,
+    db: &dyn crate::Db,
+    allocator: &'b D,
+    p: Precedence,
+) -> PrettyPrintFmt<'b, D, A>
+where
+    D: DocAllocator<'b, A>,
+    D::Doc: Clone,
+    A: SpanAnnotation,
 {
     match &**e {
         ExprKind::Var(v) => v.pretty_print(db, allocator),
@@ -128,7 +181,7 @@ where
         {
             parens_when(
                 allocator,
-                p > APP_PRECEDENCE,
+                p > 0,
                 PrettyPrintFmt(
                     allocator
                         .text("\\")
@@ -159,13 +212,615 @@ where
                     ),
             ),
         ),
+        ExprKind::TypeApp(f, type_) => parens_when(
+            allocator,
+            p > APP_PRECEDENCE,
+            PrettyPrintFmt(
+                pretty_print_expr(f, db, allocator, APP_PRECEDENCE)
+                    .0
+                    .append(allocator.text(" @"))
+                    .append(pretty_print_type_prec(type_, db, allocator, APP_PRECEDENCE + 1).0),
+            ),
+        ),
         ExprKind::DataConstructor(name) => name.pretty_print(db, allocator),
         ExprKind::Literal(Literal::Integer(x)) => PrettyPrintFmt(allocator.as_string(x)),
+        ExprKind::Literal(Literal::Float(x)) => {
+            PrettyPrintFmt(allocator.as_string(format_args!("{:?}", x.into_inner())))
+        }
         ExprKind::Literal(Literal::String(x)) => {
-            PrettyPrintFmt(allocator.as_string(format_args!("{:?}", x.to_string_lossy())))
+            PrettyPrintFmt(allocator.text(pretty_print_string(&x.0, '"')))
+        }
+        ExprKind::Literal(Literal::Char(x)) => {
+            PrettyPrintFmt(allocator.text(pretty_print_string(&[*x], '\'')))
+        }
+        ExprKind::Literal(Literal::Boolean(x)) => PrettyPrintFmt(allocator.as_string(x)),
+        ExprKind::Literal(Literal::Object(fields)) if fields.is_empty() => {
+            PrettyPrintFmt(allocator.text("{}"))
+        }
+        ExprKind::Literal(Literal::Object(fields)) => PrettyPrintFmt(
+            allocator
+                .text("{ ")
+                .append(allocator.intersperse(
+                    fields.iter().map(|(label, expr)| {
+                        allocator
+                            .text(pretty_print_label(label.text(db)))
+                            .append(": ")
+                            .append(pretty_print_expr(expr, db, allocator, 0).0)
+                    }),
+                    allocator.text(", "),
+                ))
+                .append(" }"),
+        ),
+        ExprKind::RecordUpdate(expr, updates) => parens_when(
+            allocator,
+            p > APP_PRECEDENCE + 1,
+            PrettyPrintFmt(
+                pretty_print_expr(expr, db, allocator, APP_PRECEDENCE + 1)
+                    .0
+                    .append(" ")
+                    .append(pretty_print_record_update(updates, db, allocator).0),
+            ),
+        ),
+        ExprKind::RecordUpdateSuffix(updates) => pretty_print_record_update(updates, db, allocator),
+        ExprKind::Literal(Literal::Array(items)) => PrettyPrintFmt(
+            allocator
+                .text("[")
+                .append(
+                    allocator.intersperse(
+                        items
+                            .iter()
+                            .map(|item| pretty_print_expr(item, db, allocator, 0).0),
+                        allocator.text(", "),
+                    ),
+                )
+                .append("]"),
+        ),
+        ExprKind::Accessor(expr, label) => PrettyPrintFmt(
+            pretty_print_expr(expr, db, allocator, APP_PRECEDENCE + 1)
+                .0
+                .append(".")
+                .append(pretty_print_label(label.text(db))),
+        ),
+        ExprKind::Wildcard => PrettyPrintFmt(allocator.text("_")),
+        ExprKind::Hole(name) => PrettyPrintFmt(allocator.text(format!("?{}", name.text(db)))),
+        ExprKind::Error => PrettyPrintFmt(allocator.text("<error>")),
+        ExprKind::Operator(op) => PrettyPrintFmt(
+            allocator
+                .text("(")
+                .append(pretty_print_infix_op(op, db, allocator).0)
+                .append(")"),
+        ),
+        ExprKind::Infix(first, rest) => {
+            parens_when(
+                allocator,
+                p > 0,
+                PrettyPrintFmt(pretty_print_expr(first, db, allocator, 1).0.append(
+                    allocator.concat(rest.iter().map(|(op, expr)| {
+                        allocator
+                            .text(" ")
+                            .append(pretty_print_infix_op(op, db, allocator).0)
+                            .append(" ")
+                            .append(pretty_print_expr(expr, db, allocator, 1).0)
+                    })),
+                )),
+            )
+        }
+        ExprKind::If { cond, then_, else_ } => parens_when(
+            allocator,
+            p > 0,
+            PrettyPrintFmt(
+                allocator
+                    .text("if ")
+                    .append(pretty_print_expr(cond, db, allocator, 0).0)
+                    .append(" then ")
+                    .append(pretty_print_expr(then_, db, allocator, 0).0)
+                    .append(" else ")
+                    .append(pretty_print_expr(else_, db, allocator, 0).0),
+            ),
+        ),
+        ExprKind::Let { decls, body } => parens_when(
+            allocator,
+            p > 0,
+            PrettyPrintFmt(
+                pretty_print_let_block(decls, db, allocator)
+                    .0
+                    .append(allocator.line())
+                    .group()
+                    .append("in ")
+                    .append(pretty_print_expr(body, db, allocator, 0).0),
+            ),
+        ),
+        ExprKind::Case { exprs, branches } => {
+            parens_when(
+                allocator,
+                p > 0,
+                PrettyPrintFmt(
+                    allocator
+                        .text("case ")
+                        .append(
+                            allocator.intersperse(
+                                exprs
+                                    .iter()
+                                    .map(|expr| pretty_print_expr(expr, db, allocator, 1).0),
+                                allocator.text(", "),
+                            ),
+                        )
+                        .append(" of")
+                        .append(
+                            allocator
+                                .concat(branches.iter().map(|branch| {
+                                    allocator
+                                        .hardline()
+                                        .append(allocator.intersperse(
+                                            branch.pats.iter().map(|pat| {
+                                                pretty_print_pat(pat, db, allocator, 0).0
+                                            }),
+                                            allocator.text(", "),
+                                        ))
+                                        .append(
+                                            pretty_print_guarded(
+                                                &branch.expr,
+                                                "->",
+                                                &branch.where_,
+                                                db,
+                                                allocator,
+                                            )
+                                            .0,
+                                        )
+                                }))
+                                .nest(2),
+                        ),
+                ),
+            )
+        }
+        ExprKind::Typed(expr, type_) => {
+            // The operands of an operator chain can't swallow the `::`
+            let expr_p = match &expr.1 {
+                ExprKind::Infix(..) => 0,
+                _ => 1,
+            };
+            parens_when(
+                allocator,
+                p > 0,
+                PrettyPrintFmt(
+                    pretty_print_expr(expr, db, allocator, expr_p)
+                        .0
+                        .append(" :: ")
+                        .append(type_.pretty_print(db, allocator).0),
+                ),
+            )
+        }
+        ExprKind::Do(qualifier, items) => parens_when(
+            allocator,
+            p > 0,
+            PrettyPrintFmt(
+                pretty_print_block_keyword(*qualifier, "do", db, allocator)
+                    .0
+                    .append(
+                        allocator
+                            .concat(items.iter().map(|item| {
+                                allocator
+                                    .hardline()
+                                    .append(pretty_print_do_item(item, db, allocator).0)
+                            }))
+                            .nest(2),
+                    ),
+            ),
+        ),
+        ExprKind::Ado(qualifier, items, body) if items.is_empty() => parens_when(
+            allocator,
+            p > 0,
+            PrettyPrintFmt(
+                pretty_print_block_keyword(*qualifier, "ado", db, allocator)
+                    .0
+                    .append(" in ")
+                    .append(pretty_print_expr(body, db, allocator, 0).0),
+            ),
+        ),
+        ExprKind::Ado(qualifier, items, body) => parens_when(
+            allocator,
+            p > 0,
+            PrettyPrintFmt(
+                pretty_print_block_keyword(*qualifier, "ado", db, allocator)
+                    .0
+                    .append(
+                        allocator
+                            .concat(items.iter().map(|item| {
+                                allocator
+                                    .hardline()
+                                    .append(pretty_print_do_item(item, db, allocator).0)
+                            }))
+                            .append(allocator.hardline())
+                            .append("in ")
+                            .append(pretty_print_expr(body, db, allocator, 0).0)
+                            .nest(2),
+                    ),
+            ),
+        ),
+        ExprKind::Negate(expr) => parens_when(
+            allocator,
+            p >= APP_PRECEDENCE,
+            PrettyPrintFmt(
+                allocator
+                    .text("-")
+                    .append(pretty_print_expr(expr, db, allocator, APP_PRECEDENCE).0),
+            ),
+        ),
+        // `case _ of` is a section without parentheses
+        ExprKind::Section(expr) if matches!(expr.1, ExprKind::Case { .. }) => {
+            pretty_print_expr(expr, db, allocator, p)
+        }
+        ExprKind::Section(expr) => PrettyPrintFmt(
+            allocator
+                .text("(")
+                .append(pretty_print_expr(expr, db, allocator, 0).0)
+                .append(")"),
+        ),
+        ExprKind::NamedPat(name, expr) => PrettyPrintFmt(
+            allocator
+                .text(name.text(db).clone())
+                .append("@")
+                .append(pretty_print_expr(expr, db, allocator, APP_PRECEDENCE + 2).0),
+        ),
+    }
+}
+
+/// Prints `do` or `ado`, qualified with the module providing the operations if given
+fn pretty_print_block_keyword<'b, D, A>(
+    qualifier: Option<crate::ModuleId>,
+    keyword: &'static str,
+    db: &dyn crate::Db,
+    allocator: &'b D,
+) -> PrettyPrintFmt<'b, D, A>
+where
+    D: DocAllocator<'b, A>,
+{
+    match qualifier {
+        Some(module) => PrettyPrintFmt(allocator.text(format!("{}.{}", module.name(db), keyword))),
+        None => PrettyPrintFmt(allocator.text(keyword)),
+    }
+}
+
+fn pretty_print_do_item<'b, D, A>(
+    item: &crate::ast::DoItem,
+    db: &dyn crate::Db,
+    allocator: &'b D,
+) -> PrettyPrintFmt<'b, D, A>
+where
+    D: DocAllocator<'b, A>,
+    D::Doc: Clone,
+    A: SpanAnnotation,
+{
+    use crate::ast::DoItem;
+    match item {
+        DoItem::Let(decls) => {
+            PrettyPrintFmt(pretty_print_let_block(decls, db, allocator).0.group())
+        }
+        DoItem::Expr(expr) => pretty_print_expr(expr, db, allocator, 0),
+        DoItem::Bind(pat, expr) => PrettyPrintFmt(
+            pretty_print_pat(pat, db, allocator, 0)
+                .0
+                .append(" <- ")
+                .append(pretty_print_expr(expr, db, allocator, 0).0),
+        ),
+    }
+}
+
+/// Prints `let` followed by the declarations, on the same line if grouped and there's only one
+/// that fits
+fn pretty_print_let_block<'b, D, A>(
+    decls: &[crate::ast::Declaration],
+    db: &dyn crate::Db,
+    allocator: &'b D,
+) -> PrettyPrintFmt<'b, D, A>
+where
+    D: DocAllocator<'b, A>,
+    D::Doc: Clone,
+    A: SpanAnnotation,
+{
+    PrettyPrintFmt(
+        allocator.text("let").append(
+            allocator
+                .line()
+                .append(
+                    allocator.intersperse(
+                        decls
+                            .iter()
+                            .map(|decl| pretty_print_local_declaration(decl, db, allocator)),
+                        allocator.hardline(),
+                    ),
+                )
+                .nest(2),
+        ),
+    )
+}
+
+/// Prints the braces of a record update, `{ a = x, b = y }`
+fn pretty_print_record_update<'b, D, A>(
+    updates: &[(crate::symbol::Symbol, Expr)],
+    db: &dyn crate::Db,
+    allocator: &'b D,
+) -> PrettyPrintFmt<'b, D, A>
+where
+    D: DocAllocator<'b, A>,
+    D::Doc: Clone,
+    A: SpanAnnotation,
+{
+    PrettyPrintFmt(
+        allocator
+            .text("{ ")
+            .append(allocator.intersperse(
+                updates.iter().map(|(label, expr)| {
+                    allocator
+                        .text(pretty_print_label(label.text(db)))
+                        .append(" = ")
+                        .append(pretty_print_expr(expr, db, allocator, 0).0)
+                }),
+                allocator.text(", "),
+            ))
+            .append(" }"),
+    )
+}
+
+/// Prints a string or character literal between `quote`s, escaping the characters that can't
+/// appear in it literally.
+fn pretty_print_string(chars: &[crate::string::PSChar], quote: char) -> String {
+    let mut result = String::from(quote);
+    for (i, &c) in chars.iter().enumerate() {
+        match char::from_u32(c) {
+            Some('\n') => result.push_str("\\n"),
+            Some('\t') => result.push_str("\\t"),
+            Some('\r') => result.push_str("\\r"),
+            Some('\\') => result.push_str("\\\\"),
+            Some(c) if c == quote => {
+                result.push('\\');
+                result.push(c);
+            }
+            Some(c) if !c.is_control() => result.push(c),
+            // A hex escape takes up to 6 digits, so it's padded if a digit follows
+            _ if chars
+                .get(i + 1)
+                .and_then(|&next| char::from_u32(next))
+                .is_some_and(|next| next.is_ascii_hexdigit()) =>
+            {
+                result.push_str(&format!("\\x{:06x}", c))
+            }
+            _ => result.push_str(&format!("\\x{:x}", c)),
+        }
+    }
+    result.push(quote);
+    result
+}
+
+fn pretty_print_infix_op<'b, D, A>(
+    op: &crate::ast::InfixOp,
+    db: &dyn crate::Db,
+    allocator: &'b D,
+) -> PrettyPrintFmt<'b, D, A>
+where
+    D: DocAllocator<'b, A>,
+    D::Doc: Clone,
+    A: SpanAnnotation,
+{
+    match &op.1 {
+        crate::ast::InfixOpKind::Symbol(name) => name.pretty_print(db, allocator),
+        crate::ast::InfixOpKind::Backtick(expr) => PrettyPrintFmt(
+            allocator
+                .text("`")
+                .append(pretty_print_expr(expr, db, allocator, APP_PRECEDENCE + 1).0)
+                .append("`"),
+        ),
+    }
+}
+
+/// Prints a declaration preceded by its comments, one per line. A comment spanning several lines
+/// is printed as a block comment. Returns `None` for kinds of declarations that can't be printed
+/// yet; only values, their signatures and destructuring are supported.
+fn pretty_print_declaration<'b, D, A>(
+    decl: &crate::ast::Declaration,
+    db: &dyn crate::Db,
+    allocator: &'b D,
+) -> Option<PrettyPrintFmt<'b, D, A>>
+where
+    D: DocAllocator<'b, A>,
+    D::Doc: Clone,
+    A: SpanAnnotation,
+{
+    use crate::ast::{Comment, CommentKind};
+    let comments = allocator.concat(decl.1 .0.iter().map(|Comment(text, kind)| {
+        let comment = match (kind, text.contains('\n')) {
+            (CommentKind::Ordinary, false) => format!("-- {}", text),
+            (CommentKind::Ordinary, true) => format!("{{- {} -}}", text),
+            (CommentKind::Doc, false) => format!("-- |{}", text),
+            (CommentKind::Doc, true) => format!("{{-|{}-}}", text),
+        };
+        allocator.text(comment).append(allocator.hardline())
+    }));
+    let kind = pretty_print_declaration_kind(&decl.1 .1, db, allocator)?;
+    Some(PrettyPrintFmt(
+        comments.append(annotate_span(decl.0, kind).0),
+    ))
+}
+
+/// Prints a declaration of a `let` or `where` block, which can only be a value, a signature or
+/// destructuring.
+fn pretty_print_local_declaration<'b, D, A>(
+    decl: &crate::ast::Declaration,
+    db: &dyn crate::Db,
+    allocator: &'b D,
+) -> DocBuilder<'b, D, A>
+where
+    D: DocAllocator<'b, A>,
+    D::Doc: Clone,
+    A: SpanAnnotation,
+{
+    pretty_print_declaration(decl, db, allocator)
+        .expect("let and where blocks only contain values, signatures and destructuring")
+        .0
+}
+
+fn pretty_print_declaration_kind<'b, D, A>(
+    decl: &crate::ast::DeclarationKind,
+    db: &dyn crate::Db,
+    allocator: &'b D,
+) -> Option<PrettyPrintFmt<'b, D, A>>
+where
+    D: DocAllocator<'b, A>,
+    D::Doc: Clone,
+    A: SpanAnnotation,
+{
+    use crate::ast::DeclarationKind::*;
+    Some(match decl {
+        ValueDeclaration(value) => PrettyPrintFmt(
+            allocator
+                .text(value_name(value.ident, db))
+                .append(allocator.concat(value.params.iter().map(|pat| {
+                    allocator
+                        .text(" ")
+                        .append(pretty_print_pat(pat, db, allocator, APP_PRECEDENCE + 1).0)
+                })))
+                .append(pretty_print_guarded(&value.expr, "=", &value.where_, db, allocator).0),
+        ),
+        TypeSignature(sig) => PrettyPrintFmt(
+            allocator
+                .text(value_name(sig.ident, db))
+                .append(" :: ")
+                .append(sig.r#type.pretty_print(db, allocator).0),
+        ),
+        Destructuring { pat, expr, where_ } => PrettyPrintFmt(
+            pretty_print_pat(pat, db, allocator, 0)
+                .0
+                .append(pretty_print_guarded(expr, "=", where_, db, allocator).0),
+        ),
+        _ => return None,
+    })
+}
+
+/// The name of a value as it's written in a definition, with an operator in parentheses.
+fn value_name(name: crate::symbol::Symbol, db: &dyn crate::Db) -> String {
+    let text = name.text(db);
+    if text.starts_with(|c: char| c.is_alphabetic() || c == '_') {
+        text.clone()
+    } else {
+        format!("({})", text)
+    }
+}
+
+/// Formats a sequence of declarations as source, separated by blank lines. A type signature and
+/// the equations of the same value are kept together.
+///
+/// Only value declarations and their signatures can be formatted so far; returns `None` if there
+/// are declarations of other kinds.
+pub fn format_declarations(
+    db: &dyn crate::Db,
+    decls: &[crate::ast::Declaration],
+) -> Option<String> {
+    use crate::ast::DeclarationKind::{TypeSignature, ValueDeclaration};
+    let mut output = String::new();
+    let mut previous = None;
+    for decl in decls {
+        let name = match &decl.1 .1 {
+            TypeSignature(sig) => Some(sig.ident),
+            ValueDeclaration(value) => Some(value.ident),
+            _ => None,
+        };
+        let continues_previous = matches!(decl.1 .1, ValueDeclaration(_))
+            && decl.1 .0.is_empty()
+            && name.is_some()
+            && name == previous;
+        if !output.is_empty() {
+            output.push_str(if continues_previous { "\n" } else { "\n\n" });
         }
-        _ => todo!("pretty_print expr {:?}", e),
+        let doc = pretty_print_declaration::<_, ()>(decl, db, &BoxAllocator)?;
+        output.push_str(&doc.to_string());
+        previous = name;
+    }
+    output.push('\n');
+    Some(output)
+}
+
+/// Prints the right-hand side of an equation or case branch, starting with the separator, followed
+/// by its `where` block.
+fn pretty_print_guarded<'b, D, A>(
+    expr: &crate::ast::PossiblyGuardedExpr,
+    separator: &'static str,
+    where_: &[crate::ast::Declaration],
+    db: &dyn crate::Db,
+    allocator: &'b D,
+) -> PrettyPrintFmt<'b, D, A>
+where
+    D: DocAllocator<'b, A>,
+    D::Doc: Clone,
+    A: SpanAnnotation,
+{
+    use crate::ast::expr::PossiblyGuardedExpr::*;
+    use crate::ast::Guard;
+    let PrettyPrintFmt(rhs) = match expr {
+        Unconditional(e) => PrettyPrintFmt(
+            allocator
+                .text(" ")
+                .append(separator)
+                .append(" ")
+                .append(pretty_print_expr(e, db, allocator, 0).0),
+        ),
+        Guarded(guarded) => PrettyPrintFmt(
+            allocator
+                .concat(guarded.iter().map(|guarded_expr| {
+                    allocator
+                        .hardline()
+                        .append("| ")
+                        .append(allocator.intersperse(
+                            guarded_expr.guards.iter().map(|guard| {
+                                match guard {
+                                    // A block expression would swallow the `->`/`=` that follows
+                                    Guard::Expr(e) => {
+                                        let p = match &e.1 {
+                                            ExprKind::Case { .. }
+                                            | ExprKind::Let { .. }
+                                            | ExprKind::If { .. }
+                                            | ExprKind::Lam(..)
+                                            | ExprKind::Do(..)
+                                            | ExprKind::Ado(..) => 1,
+                                            _ => 0,
+                                        };
+                                        pretty_print_expr(e, db, allocator, p).0
+                                    }
+                                    Guard::Bind(pat, e) => pretty_print_pat(pat, db, allocator, 0)
+                                        .0
+                                        .append(" <- ")
+                                        .append(pretty_print_expr(e, db, allocator, 0).0),
+                                }
+                            }),
+                            allocator.text(", "),
+                        ))
+                        .append(" ")
+                        .append(separator)
+                        .append(" ")
+                        .append(pretty_print_expr(&guarded_expr.expr, db, allocator, 0).0)
+                }))
+                .nest(2),
+        ),
+    };
+    if where_.is_empty() {
+        return PrettyPrintFmt(rhs);
     }
+    PrettyPrintFmt(
+        rhs.append(
+            allocator
+                .hardline()
+                .append("where")
+                .append(
+                    allocator
+                        .concat(where_.iter().map(|decl| {
+                            allocator
+                                .hardline()
+                                .append(pretty_print_local_declaration(decl, db, allocator))
+                        }))
+                        .nest(2),
+                )
+                .nest(2),
+        ),
+    )
 }
 
 impl PrettyPrint for QualifiedName {
@@ -177,7 +832,7 @@ impl PrettyPrint for QualifiedName {
     where
         D: DocAllocator<'b, A>,
         D::Doc: Clone,
-        A: Clone,
+        A: SpanAnnotation,
     {
         let module = self
             .module(db)
@@ -205,7 +860,7 @@ where
     where
         D: DocAllocator<'b, A>,
         D::Doc: Clone,
-        A: Clone,
+        A: SpanAnnotation,
     {
         self.1.pretty_print(db, allocator)
     }
@@ -220,7 +875,7 @@ impl PrettyPrint for Pat {
     where
         D: DocAllocator<'b, A>,
         D::Doc: Clone,
-        A: Clone,
+        A: SpanAnnotation,
     {
         pretty_print_pat(self, db, allocator, 0)
     }
@@ -230,18 +885,145 @@ fn pretty_print_pat<'b, D, A>(
     pat: &Pat,
     db: &dyn crate::Db,
     allocator: &'b D,
-    _p: Precedence,
+    p: Precedence,
+) -> PrettyPrintFmt<'b, D, A>
+where
+    D: DocAllocator<'b, A>,
+    D::Doc: Clone,
+    A: SpanAnnotation,
+{
+    annotate_span(pat.0, pretty_print_pat_kind(pat, db, allocator, p))
+}
+
+fn pretty_print_pat_kind<'b, D, A>(
+    pat: &Pat,
+    db: &dyn crate::Db,
+    allocator: &'b D,
+    p: Precedence,
 ) -> PrettyPrintFmt<'b, D, A>
 where
     D: DocAllocator<'b, A>,
     D::Doc: Clone,
-    A: Clone,
+    A: SpanAnnotation,
 {
     use crate::ast::PatKind::*;
 
     match &**pat {
         Var(v) => PrettyPrintFmt(allocator.text(v.text(db).clone())),
-        a => todo!("pretty_print not implemented for Pat {a:?}"),
+        Wildcard => PrettyPrintFmt(allocator.text("_")),
+        DataConstructorApp(name, type_args, args) if type_args.is_empty() && args.is_empty() => {
+            name.pretty_print(db, allocator)
+        }
+        DataConstructorApp(name, type_args, args) => parens_when(
+            allocator,
+            p > APP_PRECEDENCE,
+            PrettyPrintFmt(
+                name.pretty_print(db, allocator)
+                    .0
+                    .append(allocator.concat(type_args.iter().map(|type_arg| {
+                        allocator.text(" @").append(
+                            pretty_print_type_prec(type_arg, db, allocator, APP_PRECEDENCE + 1).0,
+                        )
+                    })))
+                    .append(allocator.concat(args.iter().map(|arg| {
+                        allocator
+                            .text(" ")
+                            .append(pretty_print_pat(arg, db, allocator, APP_PRECEDENCE + 1).0)
+                    }))),
+            ),
+        ),
+        Infix(first, rest) => {
+            parens_when(
+                allocator,
+                p > 0,
+                PrettyPrintFmt(pretty_print_pat(first, db, allocator, 1).0.append(
+                    allocator.concat(rest.iter().map(|(op, pat)| {
+                        allocator
+                            .text(" ")
+                            .append(op.pretty_print(db, allocator).0)
+                            .append(" ")
+                            .append(pretty_print_pat(pat, db, allocator, 1).0)
+                    })),
+                )),
+            )
+        }
+        Named(name, pat) => PrettyPrintFmt(
+            allocator
+                .text(name.text(db).clone())
+                .append("@")
+                .append(pretty_print_pat(pat, db, allocator, APP_PRECEDENCE + 1).0),
+        ),
+        Typed(pat, type_) => PrettyPrintFmt(
+            allocator
+                .text("(")
+                .append(pretty_print_pat(pat, db, allocator, 0).0)
+                .append(" :: ")
+                .append(type_.pretty_print(db, allocator).0)
+                .append(")"),
+        ),
+        // Negative literals are parsed as negation, which is not an argument
+        Literal(crate::ast::Literal::Integer(x)) => parens_when(
+            allocator,
+            *x < 0 && p > APP_PRECEDENCE,
+            PrettyPrintFmt(allocator.as_string(x)),
+        ),
+        Literal(crate::ast::Literal::Float(x)) => parens_when(
+            allocator,
+            x.into_inner() < 0.0 && p > APP_PRECEDENCE,
+            PrettyPrintFmt(allocator.as_string(format_args!("{:?}", x.into_inner()))),
+        ),
+        Literal(crate::ast::Literal::Boolean(x)) => PrettyPrintFmt(allocator.as_string(x)),
+        Literal(crate::ast::Literal::String(x)) => {
+            PrettyPrintFmt(allocator.text(pretty_print_string(&x.0, '"')))
+        }
+        Literal(crate::ast::Literal::Char(x)) => {
+            PrettyPrintFmt(allocator.text(pretty_print_string(&[*x], '\'')))
+        }
+        Literal(crate::ast::Literal::Array(items)) => PrettyPrintFmt(
+            allocator
+                .text("[")
+                .append(
+                    allocator.intersperse(
+                        items
+                            .iter()
+                            .map(|item| pretty_print_pat(item, db, allocator, 0).0),
+                        allocator.text(", "),
+                    ),
+                )
+                .append("]"),
+        ),
+        Literal(crate::ast::Literal::Object(fields)) => PrettyPrintFmt(
+            allocator
+                .text("{ ")
+                .append(allocator.intersperse(
+                    fields.iter().map(|(label, pat)| {
+                        allocator
+                            .text(label.text(db).clone())
+                            .append(": ")
+                            .append(pretty_print_pat(pat, db, allocator, 0).0)
+                    }),
+                    allocator.text(", "),
+                ))
+                .append(" }"),
+        ),
+        Record(fields) => PrettyPrintFmt(
+            allocator
+                .text("{ ")
+                .append(allocator.intersperse(
+                    fields.iter().map(|(label, pat)| {
+                        match pat {
+                            Some(pat) => allocator
+                                .text(label.text(db).clone())
+                                .append(": ")
+                                .append(pretty_print_pat(pat, db, allocator, 0).0),
+                            None => allocator.text(label.text(db).clone()),
+                        }
+                    }),
+                    allocator.text(", "),
+                ))
+                .append(" }"),
+        ),
+        Error => PrettyPrintFmt(allocator.text("<error>")),
     }
 }
 
@@ -254,13 +1036,18 @@ impl PrettyPrint for Type {
     where
         D: DocAllocator<'b, A>,
         D::Doc: Clone,
-        A: Clone,
+        A: SpanAnnotation,
     {
-        pretty_print_type(self, db, allocator, 0)
+        pretty_print_type_prec(self, db, allocator, 0)
     }
 }
 
-fn pretty_print_type<'b, D, A>(
+/// Renders a type in PureScript syntax, with parentheses only where precedence requires them.
+pub fn pretty_print_type(db: &dyn crate::Db, ty: &Type) -> String {
+    pp(db, ty).to_string()
+}
+
+fn pretty_print_type_prec<'b, D, A>(
     ty: &Type,
     db: &dyn crate::Db,
     allocator: &'b D,
@@ -269,9 +1056,10 @@ fn pretty_print_type<'b, D, A>(
 where
     D: DocAllocator<'b, A>,
     D::Doc: Clone,
-    A: Clone,
+    A: SpanAnnotation,
 {
     use crate::ast::TypeKind::*;
+    use crate::ast::WildcardKind;
 
     match &**ty {
         Var(v) => PrettyPrintFmt(allocator.text(v.text(db).clone())),
@@ -281,25 +1069,199 @@ where
             allocator,
             p > FUNCTION_TYPE_PRECEDENCE,
             PrettyPrintFmt(
-                pretty_print_type(a, db, allocator, FUNCTION_TYPE_PRECEDENCE + 1)
+                pretty_print_type_prec(a, db, allocator, FUNCTION_TYPE_PRECEDENCE + 1)
                     .0
                     .append(allocator.text(" -> "))
-                    .append(pretty_print_type(b, db, allocator, FUNCTION_TYPE_PRECEDENCE).0),
+                    .append(pretty_print_type_prec(b, db, allocator, FUNCTION_TYPE_PRECEDENCE).0),
             ),
         ),
+        TypeApp(record, row) if is_prim_record(record, db) => {
+            match &row.1 {
+                Row { fields, rest } if fields.is_empty() && rest.is_none() => {
+                    PrettyPrintFmt(allocator.text("{}"))
+                }
+                Row { fields, rest } => PrettyPrintFmt(
+                    allocator
+                        .text("{ ")
+                        .append(pretty_print_row(fields, rest, db, allocator).0)
+                        .append(" }"),
+                ),
+                _ => {
+                    parens_when(
+                        allocator,
+                        p > APP_PRECEDENCE,
+                        PrettyPrintFmt(record.pretty_print(db, allocator).0.append(" ").append(
+                            pretty_print_type_prec(row, db, allocator, APP_PRECEDENCE + 1).0,
+                        )),
+                    )
+                }
+            }
+        }
         TypeApp(a, b) => parens_when(
             allocator,
             p > APP_PRECEDENCE,
             PrettyPrintFmt(
-                pretty_print_type(a, db, allocator, APP_PRECEDENCE)
+                pretty_print_type_prec(a, db, allocator, APP_PRECEDENCE)
                     .0
                     .append(allocator.text(" "))
-                    .append(pretty_print_type(b, db, allocator, APP_PRECEDENCE + 1).0),
+                    .append(pretty_print_type_prec(b, db, allocator, APP_PRECEDENCE + 1).0),
+            ),
+        ),
+        KindApp(a, b) => parens_when(
+            allocator,
+            p > APP_PRECEDENCE,
+            PrettyPrintFmt(
+                pretty_print_type_prec(a, db, allocator, APP_PRECEDENCE)
+                    .0
+                    .append(allocator.text(" @"))
+                    .append(pretty_print_type_prec(b, db, allocator, APP_PRECEDENCE + 1).0),
+            ),
+        ),
+        ForAll { vars, body, .. } => parens_when(
+            allocator,
+            p > FUNCTION_TYPE_PRECEDENCE,
+            PrettyPrintFmt(
+                allocator
+                    .text("forall ")
+                    .append(allocator.intersperse(
+                        vars.iter().map(|(var, kind)| {
+                            match kind {
+                                None => allocator.text(var.text(db).clone()),
+                                Some(kind) => allocator
+                                    .text("(")
+                                    .append(var.text(db).clone())
+                                    .append(" :: ")
+                                    .append(pretty_print_type_prec(kind, db, allocator, 0).0)
+                                    .append(")"),
+                            }
+                        }),
+                        allocator.text(" "),
+                    ))
+                    .append(allocator.text(". "))
+                    .append(pretty_print_type_prec(body, db, allocator, 0).0),
+            ),
+        ),
+        Constrained { constraint, body } => parens_when(
+            allocator,
+            p > FUNCTION_TYPE_PRECEDENCE,
+            PrettyPrintFmt(
+                pretty_print_type_prec(constraint, db, allocator, FUNCTION_TYPE_PRECEDENCE + 1)
+                    .0
+                    .append(allocator.text(" => "))
+                    .append(
+                        pretty_print_type_prec(body, db, allocator, FUNCTION_TYPE_PRECEDENCE).0,
+                    ),
             ),
         ),
+        Wildcard(WildcardKind::Hole(name)) => {
+            PrettyPrintFmt(allocator.text(format!("?{}", name.text(db))))
+        }
+        Wildcard(WildcardKind::Unnamed | WildcardKind::Ignored) => {
+            PrettyPrintFmt(allocator.text("_"))
+        }
+        Operator(op) => PrettyPrintFmt(
+            allocator
+                .text("(")
+                .append(op.pretty_print(db, allocator).0)
+                .append(")"),
+        ),
+        TypeLevelString(s) => {
+            PrettyPrintFmt(allocator.as_string(format_args!("{:?}", s.to_string_lossy())))
+        }
+        TypeLevelInt(n) => parens_when(
+            allocator,
+            n.sign() == num::bigint::Sign::Minus,
+            PrettyPrintFmt(allocator.as_string(n)),
+        ),
+        Row { fields, rest } if fields.is_empty() && rest.is_none() => {
+            PrettyPrintFmt(allocator.text("()"))
+        }
+        Row { fields, rest } => PrettyPrintFmt(
+            allocator
+                .text("( ")
+                .append(pretty_print_row(fields, rest, db, allocator).0)
+                .append(" )"),
+        ),
+        Kinded { r#type, kind } => PrettyPrintFmt(
+            allocator
+                .text("(")
+                .append(pretty_print_type_prec(r#type, db, allocator, TYPE_INFIX_PRECEDENCE).0)
+                .append(" :: ")
+                .append(pretty_print_type_prec(kind, db, allocator, 0).0)
+                .append(")"),
+        ),
+        Infix(first, rest) => parens_when(
+            allocator,
+            p > TYPE_INFIX_PRECEDENCE,
+            PrettyPrintFmt(
+                pretty_print_type_prec(first, db, allocator, APP_PRECEDENCE)
+                    .0
+                    .append(allocator.concat(rest.iter().map(|(op, ty)| {
+                        allocator
+                            .text(" ")
+                            .append(op.pretty_print(db, allocator).0)
+                            .append(" ")
+                            .append(pretty_print_type_prec(ty, db, allocator, APP_PRECEDENCE).0)
+                    }))),
+            ),
+        ),
+        Skolem(name, ..) => PrettyPrintFmt(allocator.text(name.text(db).clone())),
         Error => PrettyPrintFmt(allocator.text("<error>")),
+    }
+}
+
+/// Contents of a row or record type without the brackets, e.g. `a :: X, b :: Y | r`
+fn pretty_print_row<'b, D, A>(
+    fields: &[(crate::symbol::Symbol, Type)],
+    rest: &Option<Box<Type>>,
+    db: &dyn crate::Db,
+    allocator: &'b D,
+) -> PrettyPrintFmt<'b, D, A>
+where
+    D: DocAllocator<'b, A>,
+    D::Doc: Clone,
+    A: SpanAnnotation,
+{
+    let has_fields = !fields.is_empty();
+    let fields = allocator.intersperse(
+        fields.iter().map(|(label, ty)| {
+            allocator
+                .text(pretty_print_label(label.text(db)))
+                .append(" :: ")
+                .append(pretty_print_type_prec(ty, db, allocator, 0).0)
+        }),
+        allocator.text(", "),
+    );
+    match rest {
+        None => PrettyPrintFmt(fields),
+        Some(rest) => PrettyPrintFmt(
+            fields
+                .append(allocator.text(if has_fields { " | " } else { "| " }))
+                .append(pretty_print_type_prec(rest, db, allocator, 0).0),
+        ),
+    }
+}
+
+/// Labels which aren't identifiers have to be quoted, e.g. `"a b"`
+fn pretty_print_label(label: &str) -> String {
+    let mut chars = label.chars();
+    let is_ident = chars.next().is_some_and(|c| c.is_lowercase() || c == '_')
+        && chars.all(|c| c.is_alphanumeric() || c == '_' || c == '\'');
+    if is_ident {
+        label.to_string()
+    } else {
+        format!("{:?}", label)
+    }
+}
 
-        a => todo!("pretty_print not implemented for {a:?} type"),
+/// Whether the type is `Prim.Record`, which the parser produces for record types `{ ... }`
+fn is_prim_record(ty: &Type, db: &dyn crate::Db) -> bool {
+    match &ty.1 {
+        crate::ast::TypeKind::TypeConstructor(name) => {
+            name.module(db).map(|module| module.name(db)).as_deref() == Some("Prim")
+                && name.name(db).text(db) == "Record"
+        }
+        _ => false,
     }
 }
 
@@ -312,7 +1274,7 @@ impl PrettyPrint for crate::indexed_module::TypeDecl {
     where
         D: DocAllocator<'b, A>,
         D::Doc: Clone,
-        A: Clone,
+        A: SpanAnnotation,
     {
         use crate::indexed_module::TypeDecl::*;
         match self {
@@ -350,7 +1312,7 @@ impl PrettyPrint for crate::indexed_module::ValueDecl {
     where
         D: DocAllocator<'b, A>,
         D::Doc: Clone,
-        A: Clone,
+        A: SpanAnnotation,
     {
         let builder = allocator
             .text("")
@@ -367,10 +1329,11 @@ impl PrettyPrint for crate::indexed_module::ValueDecl {
                     allocator.text(self.name.name(db).text(db).clone()).append(
                         allocator
                             .concat(e.pats.iter().map(|p| {
-                                allocator.text(" ").append(p.pretty_print(db, allocator).0)
+                                allocator.text(" ").append(
+                                    pretty_print_pat(p, db, allocator, APP_PRECEDENCE + 1).0,
+                                )
                             }))
-                            .append(allocator.text(" = "))
-                            .append(e.expr.pretty_print(db, allocator).0),
+                            .append(pretty_print_guarded(&e.expr, "=", &e.where_, db, allocator).0),
                     )
                 }),
                 allocator.text("\n"),
@@ -388,12 +1351,12 @@ impl PrettyPrint for crate::ast::expr::PossiblyGuardedExpr {
     where
         D: DocAllocator<'b, A>,
         D::Doc: Clone,
-        A: Clone,
+        A: SpanAnnotation,
     {
         use crate::ast::expr::PossiblyGuardedExpr::*;
         match self {
             Unconditional(e) => e.pretty_print(db, allocator),
-            Guarded(g) => todo!("pretty_print not implemented for PossiblyGuardedExpr {g:?}"),
+            Guarded(_) => pretty_print_guarded(self, "=", &[], db, allocator),
         }
     }
 }
@@ -402,10 +1365,58 @@ pub fn pp<T: PrettyPrint>(db: &dyn crate::Db, x: T) -> PrettyPrintFmt<'_, BoxAll
     x.pretty_print::<_, ()>(db, &BoxAllocator)
 }
 
+/// Maps byte ranges of the printed output to the spans of the AST nodes they were printed from.
+/// Nested nodes produce nested ranges; inner nodes come before the nodes enclosing them.
+pub type SourceMap = Vec<(Range<usize>, SourceSpan)>;
+
+/// Like `pp`, but also returns a source map of the output.
+pub fn pp_with_source_map<T: PrettyPrint>(db: &dyn crate::Db, x: T) -> (String, SourceMap) {
+    let doc = x.pretty_print::<_, SourceSpan>(db, &BoxAllocator).0;
+    let mut writer = SourceMapWriter::default();
+    doc.1
+        .render_raw(80, &mut writer)
+        .expect("writing to a String can't fail");
+    (writer.output, writer.source_map)
+}
+
+#[derive(Default)]
+struct SourceMapWriter {
+    output: String,
+    open: Vec<(usize, SourceSpan)>,
+    source_map: SourceMap,
+}
+
+impl Render for SourceMapWriter {
+    type Error = std::fmt::Error;
+
+    fn write_str(&mut self, s: &str) -> Result<usize, Self::Error> {
+        self.output.push_str(s);
+        Ok(s.len())
+    }
+
+    fn fail_doc(&self) -> Self::Error {
+        std::fmt::Error
+    }
+}
+
+impl<'a> RenderAnnotated<'a, SourceSpan> for SourceMapWriter {
+    fn push_annotation(&mut self, span: &'a SourceSpan) -> Result<(), Self::Error> {
+        self.open.push((self.output.len(), *span));
+        Ok(())
+    }
+
+    fn pop_annotation(&mut self) -> Result<(), Self::Error> {
+        let (start, span) = self.open.pop().expect("unbalanced annotations");
+        self.source_map.push((start..self.output.len(), span));
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::utils::tests::*;
+    use indoc::indoc;
 
     fn pp_type(input: &str) -> String {
         let db = &crate::Database::new();
@@ -420,6 +1431,30 @@ mod tests {
         assert_eq!(input, pp_type(input));
     }
 
+    #[test]
+    fn source_map_var() {
+        let db = &crate::Database::new();
+        let module = dummy_module(db);
+        let input = "\\x -> f   x";
+        let expr = crate::parser::parse_expr(db, input, module).1.unwrap();
+        let (output, source_map) = pp_with_source_map(db, &expr);
+        assert_eq!(output, "\\x -> f x");
+        let f_start = input.find('f').unwrap();
+        let (range, _) = source_map
+            .iter()
+            .find(|(_, span)| span.start == f_start)
+            .unwrap();
+        assert_eq!(&output[range.clone()], "f");
+        let x_start = input.rfind('x').unwrap();
+        let (range, span) = source_map
+            .iter()
+            .find(|(range, _)| range.start == output.rfind('x').unwrap())
+            .unwrap();
+        assert_eq!(&output[range.clone()], "x");
+        assert_eq!((span.start, span.end), (x_start, x_start + 1));
+        assert_eq!(source_map.last().unwrap().0, 0..output.len());
+    }
+
     #[test]
     fn function_type() {
         test_pp_type_roundtrip("a -> b -> c");
@@ -442,4 +1477,191 @@ mod tests {
     fn app_in_function_type() {
         test_pp_type_roundtrip("Maybe a -> Maybe b");
     }
+
+    #[test]
+    fn wildcard() {
+        test_pp_type_roundtrip("_ -> Maybe _");
+    }
+
+    #[test]
+    fn forall() {
+        test_pp_type_roundtrip("forall a. a -> a");
+        test_pp_type_roundtrip("forall (f :: Type -> Type) a. f a -> f a");
+        test_pp_type_roundtrip("(forall a. a -> a) -> Int");
+    }
+
+    #[test]
+    fn constrained() {
+        test_pp_type_roundtrip("forall a. Eq a => a -> a -> Boolean");
+        test_pp_type_roundtrip("Show a => Show b => a -> b -> String");
+    }
+
+    #[test]
+    fn rows_and_records() {
+        test_pp_type_roundtrip("( foo :: Int | e )");
+        test_pp_type_roundtrip("( | e )");
+        test_pp_type_roundtrip("()");
+        test_pp_type_roundtrip("{ foo :: Int, bar :: Maybe String }");
+        test_pp_type_roundtrip(r#"{ "a b" :: Int | e }"#);
+        test_pp_type_roundtrip("{}");
+        test_pp_type_roundtrip("Record ( foo :: Int ) -> { foo :: Int -> Int }");
+    }
+
+    #[test]
+    fn type_level_literals_and_operators() {
+        test_pp_type_roundtrip(r#"Proxy "sym""#);
+        test_pp_type_roundtrip("Proxy (-1) -> Proxy 2");
+        test_pp_type_roundtrip("(~>)");
+        test_pp_type_roundtrip("f ~> g");
+        test_pp_type_roundtrip("Maybe (f ~> g) -> a /\\ b -> Int");
+    }
+
+    /// Parses, prints and parses again, comparing the two parses without their spans.
+    fn assert_reparses_equal(input: &str) {
+        let db = &crate::Database::new();
+        let module = dummy_module(db);
+        assert_print_reparses(
+            input,
+            |input| crate::parser::parse_type(db, input, module),
+            |ty| pretty_print_type(db, ty),
+        );
+    }
+
+    #[test]
+    fn reparse_type() {
+        for input in [
+            "Maybe Int",
+            "Either String Int",
+            "Array (Maybe Int)",
+            "( foo :: Int, \"Bar\" :: String, data :: Int )",
+            "( foo :: Int | e )",
+            "( | e )",
+            "()",
+            "{ foo :: Int | e }",
+            r#"{ "a b" :: Int, foo :: String | e }"#,
+            "forall a b. (a -> b) -> Array a -> Array b",
+            "forall r. { x :: Int | r } -> (Int -> Int) -> Array (Maybe { y :: r })",
+        ] {
+            assert_reparses_equal(input);
+        }
+    }
+
+    fn format_expr(input: &str) -> String {
+        let db = &crate::Database::new();
+        let module = dummy_module(db);
+        assert_print_reparses(
+            input,
+            |input| crate::parser::parse_expr(db, input, module),
+            |expr| pp(db, expr).to_string(),
+        )
+    }
+
+    #[test]
+    fn format_blocks() {
+        insta::assert_snapshot!(format_expr(indoc!(
+            r#"
+            \xs -> do
+              let n = length xs
+                  go acc = case _ of
+                    [] -> acc
+                    x : rest | x > 0 -> go (acc + x) rest
+                             | otherwise -> let y = -x in go (acc - y) rest
+              log ("count: " <> show n)
+              total <- pure (if n == 0 then 0 else go 0 xs)
+              ado
+                a <- f total
+                b <- g { total: total, n: n } r { count = n }
+                in a.value + b."other field"
+            "#
+        )));
+    }
+
+    #[test]
+    fn format_literals() {
+        insta::assert_snapshot!(format_expr(
+            r#"[1.5, 6.022e23, 'a', '\'', "tab\tquote\"", "\x1f\x61", (_ + 1), (pure 1 :: Effect Int)]"#
+        ));
+    }
+
+    #[test]
+    fn format_declarations_with_comments() {
+        use crate::ast::normalize::StripSpans;
+
+        let db = &crate::Database::new();
+        let parse = |input: &str| {
+            let module_id = parse_module_id(input, db);
+            let (errors, module) = crate::parser::parse_module(db, input, module_id);
+            assert_eq!(errors, vec![]);
+            let mut declarations = module.unwrap().1 .1.declarations;
+            declarations.strip_spans();
+            declarations
+        };
+        let input = indoc!(
+            "
+            module Test where
+
+            -- | Adds one
+            -- | to a number
+            inc :: Int -> Int
+            inc x = x + 1
+
+            {- Not a doc
+               comment -}
+            -- plain
+            f 0 = 1
+            f n | n > 0 = n * f (n - 1)
+                | otherwise = 0
+              where
+              go = 1
+            "
+        );
+        let declarations = parse(input);
+        let formatted = format_declarations(db, &declarations).unwrap();
+        let reparsed = parse(&format!("module Test where\n\n{}", formatted));
+        assert_eq!(reparsed, declarations, "formatted as:\n{}", formatted);
+        insta::assert_snapshot!(formatted);
+    }
+
+    #[test]
+    fn format_declarations_unsupported() {
+        let db = &crate::Database::new();
+        let input = indoc!(
+            "
+            module Test where
+
+            data Maybe a = Just a | Nothing
+
+            x = 1
+            "
+        );
+        let module_id = parse_module_id(input, db);
+        let (errors, module) = crate::parser::parse_module(db, input, module_id);
+        assert_eq!(errors, vec![]);
+        let declarations = module.unwrap().1 .1.declarations;
+        assert_eq!(format_declarations(db, &declarations), None);
+    }
+
+    #[test]
+    fn format_operator_definition() {
+        let db = &crate::Database::new();
+        let input = indoc!(
+            "
+            module Test where
+
+            instance Semigroup Foo where
+              (<>) x _ = x
+            "
+        );
+        let module_id = parse_module_id(input, db);
+        let (errors, module) = crate::parser::parse_module(db, input, module_id);
+        assert_eq!(errors, vec![]);
+        let declarations = module.unwrap().1 .1.declarations;
+        let crate::ast::DeclarationKind::InstanceChain(instances) = &declarations[0].1 .1 else {
+            panic!("expected an instance, got {:?}", declarations[0]);
+        };
+        assert_eq!(
+            format_declarations(db, &instances[0].body).as_deref(),
+            Some("(<>) x _ = x\n")
+        );
+    }
 }
@@ -1,14 +1,38 @@
 pub const PRIM_SOURCE: &str = r"module Prim where
-data Function :: Type -> Type -> Type
-data Array :: Type -> Type
-data Record :: Row Type -> Type
-data Number :: Type
-data Int :: Type
-data String :: Type
-data Char :: Type
-data Boolean :: Type
-data Type :: Type
-data Constraint :: Type
-data Symbol :: Type
-data Row :: Type -> Type
+foreign import data Function :: Type -> Type -> Type
+foreign import data Array :: Type -> Type
+foreign import data Record :: Row Type -> Type
+foreign import data Number :: Type
+foreign import data Int :: Type
+foreign import data String :: Type
+foreign import data Char :: Type
+foreign import data Boolean :: Type
+foreign import data Type :: Type
+foreign import data Constraint :: Type
+foreign import data Symbol :: Type
+foreign import data Row :: Type -> Type
 ";
+
+pub const PRIM_ROW_SOURCE: &str = r"module Prim.Row where
+class Union (left :: Row k) (right :: Row k) (union :: Row k) | left right -> union, right union -> left, union left -> right
+class Nub (original :: Row k) (nubbed :: Row k) | original -> nubbed
+class Lacks (label :: Symbol) (row :: Row k)
+class Cons (label :: Symbol) (a :: k) (tail :: Row k) (row :: Row k) | label a tail -> row, label row -> a tail
+";
+
+pub const PRIM_ROW_LIST_SOURCE: &str = r"module Prim.RowList where
+foreign import data RowList :: Type -> Type
+foreign import data Cons :: forall k. Symbol -> k -> RowList k -> RowList k
+foreign import data Nil :: forall k. RowList k
+class RowToList (row :: Row k) (list :: RowList k) | row -> list
+";
+
+/// Source of the built-in modules that have to be imported explicitly, unlike `Prim`. They are
+/// loaded the first time they're looked up.
+pub fn builtin_module_source(name: &str) -> Option<&'static str> {
+    match name {
+        "Prim.Row" => Some(PRIM_ROW_SOURCE),
+        "Prim.RowList" => Some(PRIM_ROW_LIST_SOURCE),
+        _ => None,
+    }
+}
@@ -1,19 +1,88 @@
 use crate::ast::*;
+use crate::free_vars::pattern_vars;
 use crate::indexed_module::IndexedModule;
 use crate::indexed_module::{TypeClassDecl, TypeDecl, ValueDecl};
-use crate::renamed_module::DeclId;
+use crate::renamed_module::{DeclId, Namespace};
+use crate::scope::Scope;
+use crate::source_span::SourceSpan;
 use crate::symbol::Symbol;
 use crate::ModuleId;
 use crate::{Db, Diagnostic};
+use fxhash::FxHashMap;
 use std::collections::{HashMap, HashSet};
 
+/// A name that couldn't be resolved, or was bound incorrectly. Each variant carries the span of
+/// the offending node and the name as written.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RenameError {
+    UnknownVariable(SourceSpan, String),
+    UnknownDataConstructor(SourceSpan, String),
+    UnknownOperator(SourceSpan, String),
+    UnknownType(SourceSpan, String),
+    UnknownTypeOperator(SourceSpan, String),
+    UnknownTypeVariable(SourceSpan, String),
+    DuplicatePatternVariable(SourceSpan, String),
+    UnexpectedLetDeclaration(SourceSpan),
+    UnknownModule(SourceSpan, String),
+    UnknownExport(SourceSpan, String),
+}
+
+impl RenameError {
+    pub fn span(&self) -> SourceSpan {
+        match self {
+            Self::UnknownVariable(span, _)
+            | Self::UnknownDataConstructor(span, _)
+            | Self::UnknownOperator(span, _)
+            | Self::UnknownType(span, _)
+            | Self::UnknownTypeOperator(span, _)
+            | Self::UnknownTypeVariable(span, _)
+            | Self::DuplicatePatternVariable(span, _)
+            | Self::UnexpectedLetDeclaration(span)
+            | Self::UnknownModule(span, _)
+            | Self::UnknownExport(span, _) => *span,
+        }
+    }
+
+    pub fn message(&self) -> String {
+        match self {
+            Self::UnknownVariable(_, name) => format!("Unknown variable '{}'", name),
+            Self::UnknownDataConstructor(_, name) => format!("Unknown data constructor '{}'", name),
+            Self::UnknownOperator(_, name) => format!("Unknown operator '{}'", name),
+            Self::UnknownType(_, name) => format!("Unknown type '{}'", name),
+            Self::UnknownTypeOperator(_, name) => format!("Unknown type operator '{}'", name),
+            Self::UnknownTypeVariable(_, name) => format!("Unknown type variable '{}'", name),
+            Self::DuplicatePatternVariable(_, name) => {
+                format!("Duplicate variable '{}' in pattern", name)
+            }
+            Self::UnexpectedLetDeclaration(_) => "Unexpected declaration in let block".into(),
+            Self::UnknownModule(_, name) => format!("Unknown module '{}'", name),
+            Self::UnknownExport(_, name) => format!("Unknown export '{}'", name),
+        }
+    }
+}
+
+impl From<RenameError> for Diagnostic {
+    fn from(err: RenameError) -> Self {
+        Diagnostic::new(err.span(), err.message())
+    }
+}
+
+pub struct RenameResult {
+    pub errors: Vec<RenameError>,
+    /// Top-level names the module refers to, with the module alias they were referred to by
+    pub used_names: HashSet<(Option<ModuleId>, AbsoluteName)>,
+}
+
+/// Resolves all names in the module to absolute names, in place.
+///
+/// Unresolved names are left as they are and reported in the returned errors; the rest of the
+/// module is still renamed.
 pub fn rename_module(
     db: &dyn Db,
     module: &mut IndexedModule,
     imported_decls: &mut [(Option<ModuleId>, DeclId)],
     exported_decls: &mut [DeclId],
-    diagnostics: &mut Vec<Diagnostic>,
-) {
+) -> RenameResult {
     let exported = exported_decls.iter().map(|decl_id| {
         (
             QualifiedName::new(db, Option::None, decl_id.name(db)),
@@ -21,6 +90,35 @@ pub fn rename_module(
         )
     });
 
+    // Top-level declarations are in scope in the whole module, whether exported or not
+    let constructors = module
+        .types
+        .values()
+        .filter_map(|decl| match decl {
+            TypeDecl::Data(data) => Some(data),
+            TypeDecl::Type(_) | TypeDecl::TypeClass(_) => None,
+        })
+        .flat_map(|data| {
+            data.constructors
+                .iter()
+                .map(|constructor| AbsoluteName::new(db, module.module_id, constructor.1 .1.name))
+        })
+        .collect::<Vec<_>>();
+    let operators = module
+        .operators
+        .keys()
+        .map(|decl_id| decl_id.to_absolute_name(db))
+        .collect::<Vec<_>>();
+    let local = module
+        .values
+        .keys()
+        .chain(module.types.keys())
+        .chain(module.classes.keys())
+        .chain(constructors.iter())
+        .chain(operators.iter())
+        .map(|abs| (QualifiedName::new(db, Option::None, abs.name(db)), *abs))
+        .collect::<Vec<_>>();
+
     let module_scope = imported_decls
         .iter()
         .map(|(qualified_as, id)| {
@@ -30,38 +128,114 @@ pub fn rename_module(
             )
         })
         .chain(exported)
+        .chain(local)
         .collect::<HashMap<_, _>>();
     let mut r = Renamer {
         db,
         module_scope,
-        local_scopes: vec![HashSet::new()],
-        diagnostics,
+        local_scopes: Scope::new(),
+        type_scopes: vec![],
+        implicit_type_vars: false,
+        quantified_type_vars: None,
+        errors: vec![],
+        used_names: HashSet::new(),
     };
     module.rename(&mut r);
+    RenameResult {
+        errors: r.errors,
+        used_names: r.used_names,
+    }
 }
 
 struct Renamer<'db> {
     db: &'db dyn Db,
     /// Maps from names as appear in source code to actual absolute names
     module_scope: HashMap<QualifiedName, AbsoluteName>,
-    local_scopes: Vec<HashSet<Symbol>>,
-    diagnostics: &'db mut Vec<Diagnostic>,
+    /// Local variables, with the spans of their bindings
+    local_scopes: Scope<SourceSpan>,
+    /// Type variables bound by `forall`, type parameters and class heads
+    type_scopes: Vec<HashSet<Symbol>>,
+    /// Whether unbound type variables are implicitly quantified (as in type signatures) rather
+    /// than an error (as in data declarations and type synonyms)
+    implicit_type_vars: bool,
+    /// Implicitly quantified type variables of the `let`/`where` signature being renamed
+    quantified_type_vars: Option<Vec<Symbol>>,
+    errors: Vec<RenameError>,
+    used_names: HashSet<(Option<ModuleId>, AbsoluteName)>,
 }
 
 impl<'db> Renamer<'db> {
-    fn push_scope(&mut self) {
-        self.local_scopes.push(HashSet::new());
+    /// Looks up a top-level name, recording it as used.
+    fn resolve(&mut self, name: &QualifiedName) -> Option<AbsoluteName> {
+        let abs = *self.module_scope.get(name)?;
+        self.used_names.insert((name.module(self.db), abs));
+        Some(abs)
+    }
+
+    /// Renames types inside `f` with `vars` bound as type variables.
+    fn with_type_scope(
+        &mut self,
+        vars: impl IntoIterator<Item = Symbol>,
+        implicit_type_vars: bool,
+        f: impl FnOnce(&mut Self),
+    ) {
+        let prev_implicit_type_vars =
+            std::mem::replace(&mut self.implicit_type_vars, implicit_type_vars);
+        self.type_scopes.push(vars.into_iter().collect());
+        f(self);
+        self.type_scopes.pop();
+        self.implicit_type_vars = prev_implicit_type_vars;
+    }
+
+    fn bind_pattern_var(&mut self, span: SourceSpan, var: Symbol) {
+        if self.local_scopes.define(var, span).is_some() {
+            self.errors.push(RenameError::DuplicatePatternVariable(
+                span,
+                var.text(self.db).clone(),
+            ));
+        }
+    }
+
+    /// Checks that the qualifier of an `M.do` or `M.ado` block is the alias of an imported module.
+    /// It's kept as written, for desugaring to look up `M.bind` and friends.
+    fn check_qualifier(&mut self, span: SourceSpan, qualifier: Option<ModuleId>) {
+        let Some(module) = qualifier else {
+            return;
+        };
+        let db = self.db;
+        if !self
+            .module_scope
+            .keys()
+            .any(|name| name.module(db) == Some(module))
+        {
+            self.errors
+                .push(RenameError::UnknownModule(span, module.name(db).clone()));
+        }
     }
 
-    fn pop_scope(&mut self) {
-        let scope = self.local_scopes.pop();
-        assert!(scope.is_some(), "pop_scope called when there are no scopes");
+    fn is_local(&self, var: Symbol) -> bool {
+        self.local_scopes.lookup(var).is_some()
     }
 
-    fn top_scope(&mut self) -> &mut HashSet<Symbol> {
-        self.local_scopes
-            .last_mut()
-            .expect("top_scope called when there are no scopes")
+    fn is_type_var_bound(&self, var: Symbol) -> bool {
+        self.type_scopes.iter().any(|scope| scope.contains(&var))
+    }
+
+    fn rename_type_name(
+        &mut self,
+        span: SourceSpan,
+        name: &mut QualifiedName,
+        error: fn(SourceSpan, String) -> RenameError,
+    ) {
+        let db = self.db;
+        match self.resolve(name) {
+            Some(abs) => {
+                *name = abs.to_qualified_name(db);
+            }
+            None => self
+                .errors
+                .push(error(span, name.name(db).text(db).clone())),
+        }
     }
 }
 
@@ -81,26 +255,68 @@ where
     }
 }
 
+impl<T> Rename for Box<T>
+where
+    T: Rename,
+{
+    fn rename(&mut self, r: &mut Renamer) {
+        (**self).rename(r)
+    }
+}
+
 impl Rename for IndexedModule {
     fn rename(&mut self, r: &mut Renamer) {
-        self.values.iter_mut().for_each(|(_, v)| {
-            v.rename(r);
-        });
+        let db = r.db;
+        let values = in_source_order(db, &self.values, Namespace::Value, &self.decls_ref_loc);
+        for name in values {
+            self.values.get_mut(&name).unwrap().rename(r);
+        }
 
-        self.types.iter_mut().for_each(|(_, v)| {
-            v.rename(r);
-        });
+        let types = in_source_order(db, &self.types, Namespace::Type, &self.decls_ref_loc);
+        for name in types {
+            self.types.get_mut(&name).unwrap().rename(r);
+        }
 
-        self.classes.iter_mut().for_each(|(_, v)| {
-            v.rename(r);
-        });
+        let classes = in_source_order(db, &self.classes, Namespace::Class, &self.decls_ref_loc);
+        for name in classes {
+            self.classes.get_mut(&name).unwrap().rename(r);
+        }
     }
 }
 
+/// Keys of `decls` ordered by the position of their declarations. The order of the map itself
+/// depends on the interned ids of the names, and errors should be reported in the same order
+/// regardless.
+fn in_source_order<V>(
+    db: &dyn Db,
+    decls: &FxHashMap<AbsoluteName, V>,
+    namespace: Namespace,
+    decls_ref_loc: &FxHashMap<DeclId, usize>,
+) -> Vec<AbsoluteName> {
+    let mut names = decls.keys().copied().collect::<Vec<_>>();
+    names.sort_by_cached_key(|name| {
+        let position = decls_ref_loc.get(&name.to_decl_id(db, namespace.clone()));
+        (position.copied(), name.name(db).text(db).clone())
+    });
+    names
+}
+
 impl Rename for ValueDecl {
     fn rename(&mut self, r: &mut Renamer) {
-        self.type_.rename(r);
-        self.equations.iter_mut().for_each(|x| x.rename(r));
+        r.with_type_scope([], true, |r| self.type_.rename(r));
+        let scoped = self.type_.as_ref().map_or(vec![], scoped_type_vars);
+        r.with_type_scope(scoped, false, |r| {
+            self.equations.iter_mut().for_each(|x| x.rename(r))
+        });
+    }
+}
+
+/// Type variables bound by the outermost `forall` of a signature. They scope over the body of the
+/// declaration, including signatures in its `where` and `let` blocks.
+fn scoped_type_vars(type_: &Type) -> Vec<Symbol> {
+    match &type_.1 {
+        TypeKind::ForAll { vars, .. } => vars.iter().map(|(var, _)| *var).collect(),
+        _ => vec![],
     }
 }
 
@@ -108,14 +324,20 @@ impl Rename for TypeDecl {
     fn rename(&mut self, r: &mut Renamer) {
         match self {
             Self::Data(data) => {
-                data.constructors.iter_mut().for_each(|constructor| {
-                    for ref mut field in &mut constructor.1 .1.fields {
-                        field.rename(r);
-                    }
+                rename_type_params(r, &mut data.params);
+                r.with_type_scope(data.params.iter().map(|(name, _)| *name), false, |r| {
+                    data.constructors.iter_mut().for_each(|constructor| {
+                        for ref mut field in &mut constructor.1 .1.fields {
+                            field.rename(r);
+                        }
+                    });
                 });
             }
             Self::Type(alias) => {
-                alias.body.rename(r);
+                rename_type_params(r, &mut alias.params);
+                r.with_type_scope(alias.params.iter().map(|(name, _)| *name), false, |r| {
+                    alias.body.rename(r);
+                });
             }
             Self::TypeClass(type_class) => {
                 type_class.rename(r);
@@ -125,46 +347,111 @@ impl Rename for TypeDecl {
 }
 
 impl Rename for TypeClassDecl {
-    fn rename(&mut self, _r: &mut Renamer) {
-        // TODO: rename type classes
+    fn rename(&mut self, r: &mut Renamer) {
+        rename_type_params(r, &mut self.params);
+        r.with_type_scope(self.params.iter().map(|(name, _)| *name), false, |r| {
+            for constraint in &mut self.constraints {
+                constraint.rename(r);
+            }
+        });
+        // Method signatures may quantify over further variables implicitly
+        r.with_type_scope(self.params.iter().map(|(name, _)| *name), true, |r| {
+            for method in &mut self.methods {
+                method.r#type.rename(r);
+            }
+        });
     }
 }
 
+fn rename_type_params(r: &mut Renamer, params: &mut [TypeParameter]) {
+    // Kinds don't bind type variables of their own
+    r.with_type_scope([], true, |r| {
+        for (_, kind) in params {
+            kind.rename(r);
+        }
+    });
+}
+
 impl Rename for Type {
     fn rename(&mut self, r: &mut Renamer) {
         let type_ = &mut self.1;
         let db = r.db;
         match type_ {
-            TypeKind::TypeConstructor(name) => match r.module_scope.get(name) {
-                Some(abs) => {
-                    *name = abs.to_qualified_name(db);
+            TypeKind::TypeConstructor(name) => {
+                r.rename_type_name(self.0, name, RenameError::UnknownType)
+            }
+            TypeKind::Operator(name) => {
+                r.rename_type_name(self.0, name, RenameError::UnknownTypeOperator)
+            }
+            TypeKind::Var(var) if r.is_type_var_bound(*var) => {}
+            TypeKind::Var(var) => {
+                if !r.implicit_type_vars {
+                    r.errors.push(RenameError::UnknownTypeVariable(
+                        self.0,
+                        var.text(db).clone(),
+                    ));
+                } else if let Some(vars) = &mut r.quantified_type_vars {
+                    if !vars.contains(var) {
+                        vars.push(*var);
+                    }
                 }
-                None => r.diagnostics.push(Diagnostic::new(
-                    self.0,
-                    format!("Unknown type '{}'", name.name(db).text(db)),
-                )),
-            },
-            TypeKind::FunctionType(ref mut a, ref mut b) => {
+            }
+            TypeKind::FunctionType(ref mut a, ref mut b)
+            | TypeKind::TypeApp(ref mut a, ref mut b)
+            | TypeKind::KindApp(ref mut a, ref mut b)
+            | TypeKind::Kinded {
+                r#type: ref mut a,
+                kind: ref mut b,
+            } => {
                 a.rename(r);
                 b.rename(r);
             }
-            TypeKind::TypeApp(ref mut f, ref mut arg) => {
-                f.rename(r);
-                arg.rename(r);
+            TypeKind::ForAll { vars, body, .. } => {
+                for (_, kind) in vars.iter_mut() {
+                    kind.rename(r);
+                }
+                let implicit_type_vars = r.implicit_type_vars;
+                r.with_type_scope(vars.iter().map(|(var, _)| *var), implicit_type_vars, |r| {
+                    body.rename(r)
+                });
+            }
+            TypeKind::Constrained { constraint, body } => {
+                constraint.rename(r);
+                body.rename(r);
+            }
+            TypeKind::Row { fields, rest } => {
+                for (_, field) in fields {
+                    field.rename(r);
+                }
+                rest.rename(r);
+            }
+            TypeKind::Infix(first, rest) => {
+                first.rename(r);
+                for (op, type_) in rest {
+                    r.rename_type_name(self.0, op, RenameError::UnknownTypeOperator);
+                    type_.rename(r);
+                }
             }
-            _ => todo!("renaming TypeKind {:?} not supported", self),
+            // Wildcards and holes are left for the type checker to infer
+            TypeKind::Wildcard(_) => {}
+            TypeKind::TypeLevelString(_) | TypeKind::TypeLevelInt(_) | TypeKind::Error => {}
+            // Only introduced by the type checker
+            TypeKind::Unknown(_) | TypeKind::Skolem(..) => {}
         }
     }
 }
 
 impl Rename for CaseBranch {
     fn rename(&mut self, r: &mut Renamer) {
-        r.push_scope();
+        r.local_scopes.enter();
         for ref mut pat in &mut self.pats {
             pat.rename(r);
         }
+        r.local_scopes.enter();
+        rename_let_decls(r, &mut self.where_);
         self.expr.rename(r);
-        r.pop_scope();
+        r.local_scopes.exit();
+        r.local_scopes.exit();
     }
 }
 
@@ -172,25 +459,109 @@ impl Rename for PossiblyGuardedExpr {
     fn rename(&mut self, r: &mut Renamer) {
         match self {
             Self::Unconditional(ref mut e) => e.rename(r),
-            Self::Guarded(_) => todo!("Pattern guards not implemented"),
+            Self::Guarded(guarded) => {
+                for guarded_expr in guarded {
+                    // Pattern guards bind variables for the following guards and the body
+                    let mut scopes = 0;
+                    for guard in &mut guarded_expr.guards {
+                        match guard {
+                            Guard::Expr(expr) => expr.rename(r),
+                            Guard::Bind(pat, expr) => {
+                                expr.rename(r);
+                                r.local_scopes.enter();
+                                scopes += 1;
+                                pat.rename(r);
+                            }
+                        }
+                    }
+                    guarded_expr.expr.rename(r);
+                    for _ in 0..scopes {
+                        r.local_scopes.exit();
+                    }
+                }
+            }
         }
     }
 }
 
 impl Rename for Located<PatKind> {
     fn rename(&mut self, r: &mut Renamer) {
-        let pat = &mut self.1;
-        match pat {
-            PatKind::Var(v) => {
-                if !r.top_scope().insert(*v) {
-                    r.diagnostics.push(Diagnostic::new(
-                        self.0,
-                        format!("Duplicate variable '{}' in pattern", v.text(r.db)),
-                    ));
+        for (var, span) in pattern_vars(self) {
+            r.bind_pattern_var(span, var);
+        }
+        resolve_pat_names(r, self);
+    }
+}
+
+/// Resolves the constructors, operators and types referenced by a pattern. Its variables are bound
+/// separately.
+fn resolve_pat_names(r: &mut Renamer, pat: &mut Pat) {
+    let db = r.db;
+    let span = pat.0;
+    match &mut pat.1 {
+        PatKind::Var(_) => {}
+        PatKind::Named(_, pat) => resolve_pat_names(r, pat),
+        PatKind::DataConstructorApp(constructor_name, type_args, args) => {
+            match r.resolve(constructor_name) {
+                None => r.errors.push(RenameError::UnknownDataConstructor(
+                    span,
+                    constructor_name.name(db).text(db).clone(),
+                )),
+                Some(abs) => {
+                    *constructor_name = abs.to_qualified_name(db);
+                }
+            }
+            for type_arg in type_args {
+                r.with_type_scope([], true, |r| type_arg.rename(r));
+            }
+            for arg in args {
+                resolve_pat_names(r, arg);
+            }
+        }
+        PatKind::Infix(first, rest) => {
+            resolve_pat_names(r, first);
+            for (op, pat) in rest {
+                match r.resolve(op) {
+                    None => r.errors.push(RenameError::UnknownOperator(
+                        span,
+                        op.name(db).text(db).clone(),
+                    )),
+                    Some(abs) => {
+                        *op = abs.to_qualified_name(db);
+                    }
                 }
+                resolve_pat_names(r, pat);
+            }
+        }
+        PatKind::Literal(Literal::Array(items)) => {
+            for item in items {
+                resolve_pat_names(r, item);
             }
-            _ => todo!("renaming PatKind {:?} not supported", self),
         }
+        PatKind::Literal(Literal::Object(fields)) => {
+            for (_, field) in fields {
+                resolve_pat_names(r, field);
+            }
+        }
+        PatKind::Literal(
+            Literal::Integer(_)
+            | Literal::Float(_)
+            | Literal::String(_)
+            | Literal::Char(_)
+            | Literal::Boolean(_),
+        ) => {}
+        PatKind::Typed(pat, type_) => {
+            resolve_pat_names(r, pat);
+            r.with_type_scope([], true, |r| type_.rename(r));
+        }
+        PatKind::Record(fields) => {
+            for (_, field) in fields {
+                if let Some(field) = field {
+                    resolve_pat_names(r, field);
+                }
+            }
+        }
+        PatKind::Wildcard | PatKind::Error => {}
     }
 }
 
@@ -200,29 +571,26 @@ impl Rename for Located<ExprKind> {
         match expr {
             ExprKind::Var(ref mut v) => {
                 let db = r.db;
-                let local_vars = r.top_scope();
-                let is_local = v.module(db).is_none() && local_vars.contains(&v.name(db));
+                let is_local = v.module(db).is_none() && r.is_local(v.name(db));
                 if !is_local {
-                    match r.module_scope.get(v) {
-                        None => r.diagnostics.push(Diagnostic::new(
+                    match r.resolve(v) {
+                        None => r.errors.push(RenameError::UnknownVariable(
                             self.0,
-                            format!("Unknown variable '{}'", v.name(db).text(db)),
+                            v.name(db).text(db).clone(),
                         )),
                         Some(abs) => {
                             *v = abs.to_qualified_name(db);
-                            use salsa::DebugWithDb;
-                            eprintln!("replacing with {:?}", v.into_debug_all(db));
                         }
                     }
                 }
             }
             ExprKind::Lam(ref mut pats, ref mut expr) => {
-                r.push_scope();
+                r.local_scopes.enter();
                 for ref mut pat in pats {
                     pat.rename(r);
                 }
                 expr.rename(r);
-                r.pop_scope();
+                r.local_scopes.exit();
             }
             ExprKind::App(ref mut expr, ref mut exprs) => {
                 expr.rename(r);
@@ -230,28 +598,219 @@ impl Rename for Located<ExprKind> {
                     expr.rename(r);
                 }
             }
-            ExprKind::Literal(_) => {}
+            ExprKind::Literal(Literal::Array(items)) => {
+                for item in items {
+                    item.rename(r);
+                }
+            }
+            ExprKind::Literal(Literal::Object(fields)) => {
+                for (_, field) in fields {
+                    field.rename(r);
+                }
+            }
+            ExprKind::Literal(
+                Literal::Integer(_)
+                | Literal::Float(_)
+                | Literal::String(_)
+                | Literal::Char(_)
+                | Literal::Boolean(_),
+            ) => {}
             ExprKind::DataConstructor(constructor_name) => {
                 let db = r.db;
-                match r.module_scope.get(constructor_name) {
-                    None => r.diagnostics.push(Diagnostic::new(
+                match r.resolve(constructor_name) {
+                    None => r.errors.push(RenameError::UnknownDataConstructor(
                         self.0,
-                        format!(
-                            "Unknown data constructor variable '{}'",
-                            constructor_name.name(db).text(db)
-                        ),
+                        constructor_name.name(db).text(db).clone(),
                     )),
-
                     Some(abs) => {
                         *constructor_name = abs.to_qualified_name(db);
                     }
                 }
             }
-            _ => todo!("renaming ExprKind {:?} not supported", self),
+            ExprKind::Infix(first, rest) => {
+                first.rename(r);
+                for (op, expr) in rest {
+                    op.rename(r);
+                    expr.rename(r);
+                }
+            }
+            ExprKind::Operator(op) => op.rename(r),
+            ExprKind::Accessor(expr, _)
+            | ExprKind::Negate(expr)
+            | ExprKind::Section(expr)
+            | ExprKind::NamedPat(_, expr) => expr.rename(r),
+            ExprKind::RecordUpdate(expr, updates) => {
+                expr.rename(r);
+                for (_, value) in updates {
+                    value.rename(r);
+                }
+            }
+            ExprKind::RecordUpdateSuffix(updates) => {
+                for (_, value) in updates {
+                    value.rename(r);
+                }
+            }
+            ExprKind::Case { exprs, branches } => {
+                for expr in exprs {
+                    expr.rename(r);
+                }
+                for branch in branches {
+                    branch.rename(r);
+                }
+            }
+            ExprKind::If { cond, then_, else_ } => {
+                cond.rename(r);
+                then_.rename(r);
+                else_.rename(r);
+            }
+            ExprKind::Typed(expr, type_) | ExprKind::TypeApp(expr, type_) => {
+                expr.rename(r);
+                r.with_type_scope([], true, |r| type_.rename(r));
+            }
+            ExprKind::Let { decls, body } => {
+                r.local_scopes.enter();
+                rename_let_decls(r, decls);
+                body.rename(r);
+                r.local_scopes.exit();
+            }
+            ExprKind::Do(qualifier, items) => {
+                r.check_qualifier(self.0, *qualifier);
+                rename_do_items(r, items, None);
+            }
+            ExprKind::Ado(qualifier, items, body) => {
+                r.check_qualifier(self.0, *qualifier);
+                rename_do_items(r, items, Some(body));
+            }
+            ExprKind::Wildcard | ExprKind::Hole(_) | ExprKind::Error => {}
+        }
+    }
+}
+
+impl Rename for InfixOp {
+    fn rename(&mut self, r: &mut Renamer) {
+        let db = r.db;
+        match &mut self.1 {
+            InfixOpKind::Symbol(op) => match r.resolve(op) {
+                None => r.errors.push(RenameError::UnknownOperator(
+                    self.0,
+                    op.name(db).text(db).clone(),
+                )),
+                Some(abs) => {
+                    *op = abs.to_qualified_name(db);
+                }
+            },
+            InfixOpKind::Backtick(expr) => expr.rename(r),
+        }
+    }
+}
+
+/// Renames `let`/`where` declarations in the current scope. All declared names are bound first,
+/// so that the declarations can refer to each other.
+fn rename_let_decls(r: &mut Renamer, decls: &mut [Declaration]) {
+    for decl in decls.iter_mut() {
+        match &mut decl.1 .1 {
+            // Multiple equations of one function share the name, so no duplicate check here
+            DeclarationKind::ValueDeclaration(value) => {
+                r.local_scopes.define(value.ident, decl.0);
+            }
+            DeclarationKind::Destructuring { pat, .. } => pat.rename(r),
+            _ => {}
+        }
+    }
+    let scoped = decls
+        .iter()
+        .filter_map(|decl| match &decl.1 .1 {
+            DeclarationKind::TypeSignature(sig) => Some((sig.ident, scoped_type_vars(&sig.r#type))),
+            _ => None,
+        })
+        .collect::<HashMap<_, _>>();
+    for decl in decls.iter_mut() {
+        match &mut decl.1 .1 {
+            DeclarationKind::ValueDeclaration(value) => {
+                r.local_scopes.enter();
+                let vars = scoped.get(&value.ident).cloned().unwrap_or_default();
+                r.with_type_scope(vars, false, |r| {
+                    for pat in &mut value.params {
+                        pat.rename(r);
+                    }
+                    r.local_scopes.enter();
+                    rename_let_decls(r, &mut value.where_);
+                    value.expr.rename(r);
+                    r.local_scopes.exit();
+                });
+                r.local_scopes.exit();
+            }
+            DeclarationKind::Destructuring { expr, where_, .. } => {
+                r.local_scopes.enter();
+                rename_let_decls(r, where_);
+                expr.rename(r);
+                r.local_scopes.exit();
+            }
+            DeclarationKind::TypeSignature(sig) => {
+                // Variables bound by an enclosing signature refer to that signature's binders;
+                // the rest are quantified here, to keep the two apart in the renamed signature.
+                let outer = r.quantified_type_vars.replace(vec![]);
+                r.with_type_scope([], true, |r| sig.r#type.rename(r));
+                let vars = std::mem::replace(&mut r.quantified_type_vars, outer)
+                    .expect("should be collecting type variables");
+                if !vars.is_empty() {
+                    let span = sig.r#type.0;
+                    let body = std::mem::replace(&mut sig.r#type, Located(span, TypeKind::Error));
+                    sig.r#type = Located(
+                        span,
+                        TypeKind::ForAll {
+                            vars: vars.into_iter().map(|var| (var, None)).collect(),
+                            body: Box::new(body),
+                            skolem_scope: None,
+                        },
+                    );
+                }
+            }
+            _ => r.errors.push(RenameError::UnexpectedLetDeclaration(decl.0)),
         }
     }
 }
 
+/// Renames the statements of a `do` block, or of an `ado` block with its `in` expression. Every
+/// statement that binds variables opens a new scope, so that later binds can shadow earlier ones.
+/// In `ado`, the binds are only in scope in the `in` expression.
+fn rename_do_items(r: &mut Renamer, items: &mut [DoItem], body: Option<&mut Expr>) {
+    let mut scopes = 0;
+    // In `ado`, binds are only visible in the final `in` expression
+    let mut ado_binders = vec![];
+    for item in items {
+        match item {
+            DoItem::Expr(expr) => expr.rename(r),
+            DoItem::Bind(pat, expr) => {
+                expr.rename(r);
+                if body.is_some() {
+                    ado_binders.push(pat);
+                } else {
+                    r.local_scopes.enter();
+                    scopes += 1;
+                    pat.rename(r);
+                }
+            }
+            DoItem::Let(decls) => {
+                r.local_scopes.enter();
+                scopes += 1;
+                rename_let_decls(r, decls);
+            }
+        }
+    }
+    if let Some(body) = body {
+        r.local_scopes.enter();
+        for pat in ado_binders {
+            pat.rename(r);
+        }
+        body.rename(r);
+        r.local_scopes.exit();
+    }
+    for _ in 0..scopes {
+        r.local_scopes.exit();
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -268,18 +827,24 @@ mod test {
                 .unwrap();
         });
 
+        rename_in_db(db, module_id)
+    }
+
+    fn rename_files(files: &[(&str, &str)], module_name: &str) -> String {
+        let db = &crate::Database::test_multi_file_db(files);
+        let module_id = ModuleId::new(db, module_name.into());
+        rename_in_db(db, module_id)
+    }
+
+    fn rename_in_db(db: &crate::Database, module_id: ModuleId) -> String {
         let mut module = crate::indexed_module::indexed_module(db, module_id);
         let mut imported = crate::renamed_module::imported_decls(db, module_id);
         let mut exported = crate::renamed_module::exported_decls(db, module_id);
-        let mut diagnostics = vec![];
-
-        rename_module(
-            db,
-            &mut module,
-            &mut imported,
-            &mut exported,
-            &mut diagnostics,
-        );
+        let diagnostics = rename_module(db, &mut module, &mut imported, &mut exported)
+            .errors
+            .into_iter()
+            .map(Diagnostic::from)
+            .collect::<Vec<_>>();
 
         let types = module
             .types
@@ -297,6 +862,42 @@ mod test {
         format!("{}\n\n{:?}", types, diagnostics)
     }
 
+    #[test]
+    fn diagnostics_in_source_order() {
+        let input = indoc!(
+            "
+            module Test where
+            b = x1
+            d = x2
+            a = x3
+            c = x4
+            "
+        );
+        let messages = |reverse_interning: bool| {
+            let db = &crate::Database::test_single_file_db(input);
+            let module_id = ModuleId::new(db, "Test".into());
+            if reverse_interning {
+                // Changes the order of the names in the hash maps of the indexed module
+                for name in ["c", "a", "d", "b"] {
+                    AbsoluteName::new(db, module_id, Symbol::new(db, name.into()));
+                }
+            }
+            let mut module = crate::indexed_module::indexed_module(db, module_id);
+            let mut imported = crate::renamed_module::imported_decls(db, module_id);
+            let mut exported = crate::renamed_module::exported_decls(db, module_id);
+            rename_module(db, &mut module, &mut imported, &mut exported)
+                .errors
+                .into_iter()
+                .map(|error| Diagnostic::from(error).message)
+                .collect::<Vec<_>>()
+        };
+        let expected = (1..=4)
+            .map(|i| format!("Unknown variable 'x{}'", i))
+            .collect::<Vec<_>>();
+        assert_eq!(messages(false), expected);
+        assert_eq!(messages(true), expected);
+    }
+
     #[test]
     fn smoke() {
         assert_snapshot!(rename_mod(
@@ -356,6 +957,76 @@ mod test {
         ))
     }
 
+    #[test]
+    fn multi_file_import() {
+        assert_snapshot!(rename_files(
+            &[
+                (
+                    "Main.purs",
+                    indoc!(
+                        "
+                module Main where
+
+                import Lib (greeting)
+
+                main = greeting
+                "
+                    )
+                ),
+                (
+                    "Lib.purs",
+                    indoc!(
+                        "
+                module Lib where
+
+                greeting = 42
+                "
+                    )
+                ),
+            ],
+            "Main"
+        ))
+    }
+
+    #[test]
+    fn imported_class_method() {
+        assert_snapshot!(rename_mod(
+            indoc!(
+                "
+        module Test where
+
+        import Lib
+
+        foo = show 1
+        "
+            ),
+            vec![indoc!(
+                "
+        module Lib where
+
+        class Show a where
+          show :: a -> String
+        "
+            )]
+        ))
+    }
+
+    #[test]
+    fn recursive_value() {
+        assert_snapshot!(rename_mod(
+            indoc!(
+                "
+        module Test (g) where
+
+        f = f
+
+        g = f
+        "
+            ),
+            vec![]
+        ))
+    }
+
     #[test]
     fn duplicate_var_in_pattern() {
         assert_snapshot!(rename_mod(
@@ -384,6 +1055,179 @@ mod test {
         ))
     }
 
+    #[test]
+    fn case_in_guard() {
+        assert_snapshot!(rename_mod(
+            indoc!(
+                "
+            module Test where
+            import Lib
+            f x
+              | (case x of
+                  Just y -> isZero y
+                  _ -> false) = x
+              | otherwise = Nothing
+            "
+            ),
+            vec![indoc!(
+                "
+            module Lib where
+            data Maybe a = Nothing | Just a
+            isZero x = x
+            otherwise = true
+            "
+            )]
+        ))
+    }
+
+    #[test]
+    fn qualified_import_hides_unqualified_names() {
+        assert_snapshot!(rename_mod(
+            indoc!(
+                "
+            module Test where
+            import Lib as Q
+            a = Q.f
+            b = f
+            "
+            ),
+            vec![indoc!(
+                "
+            module Lib where
+            f = 1
+            "
+            )]
+        ))
+    }
+
+    #[test]
+    fn accessor_on_qualified_var() {
+        assert_snapshot!(rename_mod(
+            indoc!(
+                "
+            module Test where
+            import Config as Config
+            a = Config.defaults.timeout
+            "
+            ),
+            vec![indoc!(
+                "
+            module Config where
+            defaults = { timeout: 1 }
+            "
+            )]
+        ))
+    }
+
+    #[test]
+    fn operator_data_constructor() {
+        assert_snapshot!(rename_mod(
+            indoc!(
+                "
+            module Test where
+            data NonEmpty a = (:|) a (Array a)
+            prefixed = (:|) 1 []
+            infixed = 1 :| []
+            head (x :| _) = x
+            "
+            ),
+            vec![]
+        ))
+    }
+
+    #[test]
+    fn ado_binders_only_in_scope_in_body() {
+        let input = indoc!(
+            "
+            module Test where
+            f g h = ado
+              a <- g
+              b <- h a
+              in a b
+            "
+        );
+        let db = &crate::Database::test_single_file_db(input);
+        let module_id = ModuleId::new(db, "Test".into());
+        let mut module = crate::indexed_module::indexed_module(db, module_id);
+        let mut imported = crate::renamed_module::imported_decls(db, module_id);
+        let mut exported = crate::renamed_module::exported_decls(db, module_id);
+
+        let errors = rename_module(db, &mut module, &mut imported, &mut exported).errors;
+
+        let start = input.find("h a").unwrap() + 2;
+        assert_eq!(errors.len(), 1, "{:?}", errors);
+        assert!(matches!(&errors[0], RenameError::UnknownVariable(_, name) if name == "a"));
+        assert_eq!(
+            (errors[0].span().start, errors[0].span().end),
+            (start, start + 1)
+        );
+    }
+
+    #[test]
+    fn unknown_var_does_not_stop_renaming() {
+        let input = indoc!(
+            "
+            module Test where
+            import Lib
+            g = x
+            h = f
+            "
+        );
+        let db = &mut crate::Database::test_single_file_db(input);
+        db.add_source_file("Lib.purs".into(), "module Lib where\nf = 1\n".into())
+            .unwrap();
+        let module_id = ModuleId::new(db, "Test".into());
+        let mut module = crate::indexed_module::indexed_module(db, module_id);
+        let mut imported = crate::renamed_module::imported_decls(db, module_id);
+        let mut exported = crate::renamed_module::exported_decls(db, module_id);
+
+        let errors = rename_module(db, &mut module, &mut imported, &mut exported).errors;
+
+        let start = input.find("= x").unwrap() + 2;
+        assert_eq!(errors.len(), 1);
+        assert!(matches!(&errors[0], RenameError::UnknownVariable(_, name) if name == "x"));
+        assert_eq!(
+            (errors[0].span().start, errors[0].span().end),
+            (start, start + 1)
+        );
+        let h = module
+            .values
+            .iter()
+            .find(|(name, _)| name.name(db).text(db) == "h")
+            .map(|(_, decl)| format!("{}", pp(db, decl.clone())))
+            .unwrap();
+        assert_eq!(h, "h = Lib.f");
+    }
+
+    #[test]
+    fn qualified_do_needs_imported_module() {
+        let input = indoc!(
+            "
+            module Test where
+            import Lib as L
+            a = L.do
+              L.f
+            b = M.ado
+              x <- L.f
+              in x
+            "
+        );
+        let db = &mut crate::Database::test_single_file_db(input);
+        db.add_source_file("Lib.purs".into(), "module Lib where\nf = 1\n".into())
+            .unwrap();
+        let module_id = ModuleId::new(db, "Test".into());
+        let mut module = crate::indexed_module::indexed_module(db, module_id);
+        let mut imported = crate::renamed_module::imported_decls(db, module_id);
+        let mut exported = crate::renamed_module::exported_decls(db, module_id);
+
+        let errors = rename_module(db, &mut module, &mut imported, &mut exported).errors;
+
+        let start = input.find("M.ado").unwrap();
+        assert_eq!(errors.len(), 1);
+        assert!(matches!(&errors[0], RenameError::UnknownModule(_, name) if name == "M"));
+        assert_eq!(errors[0].span().start, start);
+    }
+
     #[test]
     fn rename_types() {
         assert_snapshot!(rename_mod(
@@ -425,6 +1269,123 @@ mod test {
         ))
     }
 
+    #[test]
+    fn typed_declaration() {
+        assert_snapshot!(rename_mod(
+            indoc!(
+                "module Test where
+                import Lib
+
+                fromMaybe :: forall a. a -> Maybe a -> a
+                fromMaybe x m = x
+
+                apply :: forall f. (forall a. f a -> f a) -> f A -> f A
+                apply g y = g y
+
+                type Bad = b
+            "
+            ),
+            vec![indoc!(
+                "module Lib where
+
+                data Maybe a = Nothing | Just a
+                data A
+            "
+            )]
+        ))
+    }
+
+    #[test]
+    fn nested_patterns() {
+        assert_snapshot!(rename_mod(
+            indoc!(
+                "module Test where
+                import Lib
+
+                f (Just { a: Tuple x [y, 1], b: r@(Just _) }) = x
+                f z = z
+
+                g (Just x) (Just x) = x
+            "
+            ),
+            vec![indoc!(
+                "module Lib where
+
+                data Maybe a = Nothing | Just a
+                data Tuple a b = Tuple a b
+            "
+            )]
+        ))
+    }
+
+    #[test]
+    fn record_pattern_binders() {
+        assert_snapshot!(rename_mod(
+            indoc!(
+                "module Test where
+                import Lib
+
+                f { x } { y: Just z } { a, b: Tuple c _ } = [x, z, a, c]
+            "
+            ),
+            vec![indoc!(
+                "module Lib where
+
+                data Maybe a = Nothing | Just a
+                data Tuple a b = Tuple a b
+            "
+            )]
+        ))
+    }
+
+    #[test]
+    fn guard_constructor_bind() {
+        assert_snapshot!(rename_mod(
+            indoc!(
+                "module Test where
+                import Lib
+
+                f k m
+                  | Just x <- lookup k m, isBig x = x
+                  | otherwise = k
+            "
+            ),
+            vec![indoc!(
+                "module Lib where
+
+                data Maybe a = Nothing | Just a
+                lookup k m = Nothing
+                isBig x = true
+                otherwise = true
+            "
+            )]
+        ))
+    }
+
+    #[test]
+    fn let_case_lambda() {
+        assert_snapshot!(rename_mod(
+            indoc!(
+                "module Test where
+                import Lib
+
+                f m = let
+                    g = \\x -> case x of
+                      Just y | Just z <- m -> z
+                      _ -> default
+                    default = m
+                  in g m
+            "
+            ),
+            vec![indoc!(
+                "module Lib where
+
+                data Maybe a = Nothing | Just a
+            "
+            )]
+        ))
+    }
+
     #[test]
     fn prim_explicit_blanket() {
         assert_snapshot!(rename_mod(
@@ -463,6 +1424,172 @@ mod test {
         ))
     }
 
+    #[test]
+    fn prim_row_class_constraint() {
+        assert_snapshot!(rename_mod(
+            indoc!(
+                "module Test where
+                import Prim.Row as Row
+                import Prim.RowList (class RowToList)
+                f :: forall l r r' rl. Row.Cons l Int r r' => RowToList r' rl => Record r' -> Int
+                f _ = 1
+            "
+            ),
+            vec![]
+        ))
+    }
+
+    #[test]
+    fn qualified_class_constraint() {
+        assert_snapshot!(rename_mod(
+            indoc!(
+                "module Test where
+                import Lib as L
+                class L.Eq a <= Ord a where
+                  compare :: a -> a -> Int
+                f :: forall a. L.Eq a => Ord a => a -> Int
+                f _ = 1
+            "
+            ),
+            vec![indoc!(
+                "module Lib where
+                class Eq a
+            "
+            )]
+        ))
+    }
+
+    #[test]
+    fn unknown_qualified_class() {
+        let input = indoc!(
+            "
+            module Test where
+            import Lib as L
+            f :: forall a. L.Show a => a -> Int
+            f _ = 1
+            "
+        );
+        let db = &mut crate::Database::test_single_file_db(input);
+        db.add_source_file("Lib.purs".into(), "module Lib where\nclass Eq a\n".into())
+            .unwrap();
+        let module_id = ModuleId::new(db, "Test".into());
+        let mut module = crate::indexed_module::indexed_module(db, module_id);
+        let mut imported = crate::renamed_module::imported_decls(db, module_id);
+        let mut exported = crate::renamed_module::exported_decls(db, module_id);
+
+        let errors = rename_module(db, &mut module, &mut imported, &mut exported).errors;
+
+        assert_eq!(errors.len(), 1);
+        assert!(matches!(&errors[0], RenameError::UnknownType(_, name) if name == "Show"));
+        assert_eq!(errors[0].span().start, input.find("L.Show").unwrap());
+    }
+
+    #[test]
+    fn type_wildcard() {
+        assert_snapshot!(rename_mod(
+            indoc!(
+                "module Test where
+                f :: _ -> Int
+                f x = x
+            "
+            ),
+            vec![]
+        ))
+    }
+
+    #[test]
+    fn case_multiple_scrutinees() {
+        assert_snapshot!(rename_mod(
+            indoc!(
+                "module Test where
+                data Maybe a = Just a | Nothing
+                f a b = case a, b of
+                  Just x, Just y -> x y
+                  _, y -> y
+            "
+            ),
+            vec![]
+        ))
+    }
+
+    #[test]
+    fn type_hole() {
+        assert_snapshot!(rename_mod(
+            indoc!(
+                "module Test where
+                f :: ?t -> Int
+                f x = 1
+            "
+            ),
+            vec![]
+        ))
+    }
+
+    #[test]
+    fn where_helper() {
+        assert_snapshot!(rename_mod(
+            indoc!(
+                "module Test where
+                f x = helper x
+                  where
+                  helper y = const y x
+                  const a _ = a
+            "
+            ),
+            vec![]
+        ))
+    }
+
+    #[test]
+    fn where_shared_by_guards() {
+        assert_snapshot!(rename_mod(
+            indoc!(
+                "module Test where
+                f x
+                  | small x = x
+                  | otherwise = limit
+                  where
+                  limit = 10
+                  small _ = otherwise
+                  otherwise = true
+                g x = case x of
+                  y | small y -> y
+                    | otherwise -> limit
+                    where
+                    limit = 10
+                    small _ = otherwise
+                    otherwise = true
+            "
+            ),
+            vec![]
+        ))
+    }
+
+    #[test]
+    fn where_signature_scoped_type_vars() {
+        assert_snapshot!(rename_mod(
+            indoc!(
+                "module Test where
+                identity :: forall a. a -> a
+                identity x = x
+
+                f :: forall a. a -> a
+                f x = g x
+                  where
+                  g :: a -> a
+                  g = identity
+
+                h :: b -> b
+                h x = k x
+                  where
+                  k :: b -> b
+                  k = identity
+            "
+            ),
+            vec![]
+        ))
+    }
+
     #[test]
     #[ignore = "How should this even work?"]
     fn prim_shadowed() {
@@ -3,7 +3,8 @@ use crate::ast::DeclarationRefConstructors;
 use crate::indexed_module::TypeClassDecl;
 use crate::indexed_module::TypeDecl;
 use crate::scc::SccId;
-use crate::Diagnostics;
+use crate::source_span::SourceSpan;
+use crate::{Diagnostic, Diagnostics, Severity};
 use fxhash::FxHashMap;
 use std::collections::{HashMap, HashSet};
 
@@ -12,11 +13,14 @@ use salsa::DebugWithDb;
 use petgraph::{algo::tarjan_scc, prelude::DiGraph};
 
 use crate::{
-    ast::{Declaration, DeclarationRefKind, ImportDeclarationKind},
+    ast::{
+        Declaration, DeclarationKind, DeclarationRef, DeclarationRefKind, ImportDeclarationKind,
+        ImportInner, Module, OperatorTarget,
+    },
     indexed_module::{IndexedModule, ValueDecl},
-    rename::rename_module,
+    rename::{rename_module, RenameError},
     symbol::Symbol,
-    Db, ModuleId, ParsedModule,
+    Db, ModuleId,
 };
 
 #[salsa::interned]
@@ -27,11 +31,12 @@ pub struct DeclId {
 }
 
 impl DeclId {
-    fn to_absolute_name(self, db: &dyn Db) -> AbsoluteName {
+    pub fn to_absolute_name(self, db: &dyn Db) -> AbsoluteName {
         AbsoluteName::new(db, self.module(db), self.name(db))
     }
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(PartialEq, Eq, Clone, Debug, DebugWithDb, Hash)]
 pub enum Namespace {
     Class,
@@ -47,6 +52,8 @@ pub struct RenamedModule {
     pub types: FxHashMap<AbsoluteName, TypeDecl>,
     pub values: FxHashMap<AbsoluteName, ValueDecl>,
     pub classes: FxHashMap<AbsoluteName, TypeClassDecl>,
+    /// Top-level names the module refers to, with the module alias they were referred to by
+    pub used_names: HashSet<(Option<ModuleId>, AbsoluteName)>,
 }
 
 impl RenamedModule {
@@ -88,28 +95,21 @@ pub fn renamed_module(db: &dyn Db, module_id: ModuleId) -> RenamedModule {
     let mut imported = crate::renamed_module::imported_decls(db, module_id);
     let mut exported = crate::renamed_module::exported_decls(db, module_id);
 
-    let mut diagnostics = vec![];
-
     let module = crate::parsed_module(db, module_id);
 
-    rename_module(
-        db,
-        &mut indexed,
-        &mut imported,
-        &mut exported,
-        &mut diagnostics,
-    );
-
-    diagnostics
+    let renamed = rename_module(db, &mut indexed, &mut imported, &mut exported);
+    renamed
+        .errors
         .into_iter()
-        .for_each(|d| Diagnostics::push(db, d));
+        .chain(check_operator_exports(db, &module.ast, &imported))
+        .for_each(|err| Diagnostics::push(db, err.into()));
 
-    let mut graph = DiGraph::<Declaration, ()>::new();
+    let mut graph = DiGraph::<&Declaration, ()>::new();
     let mut node_indices = HashMap::new();
 
     for declaration in module.ast.declarations.iter() {
-        let node_index = graph.add_node(declaration.clone());
-        node_indices.insert(declaration.clone(), node_index);
+        let node_index = graph.add_node(declaration);
+        node_indices.insert(declaration, node_index);
     }
 
     // TODO: Add edges between nodes based on data structure
@@ -134,77 +134,226 @@ pub fn renamed_module(db: &dyn Db, module_id: ModuleId) -> RenamedModule {
 
     RenamedModule {
         module_id,
-        imported: imported.clone(),
-        exported: exported.clone(),
+        imported,
+        exported,
         values: indexed.values,
         types: indexed.types,
         classes: indexed.classes,
+        used_names: renamed.used_names,
     }
 }
 
-struct ExportedDeclExtractor<'a> {
-    db: &'a dyn Db,
-    module_id: ModuleId,
-    exported_decls: Vec<DeclId>,
-    imported_decls: Vec<(Option<ModuleId>, DeclId)>,
+/// Computes the declarations a module makes visible to its importers, in export list order and
+/// without duplicates.
+///
+/// Without an export list, everything declared in the module is exported. `Foo(..)` expands to
+/// all constructors of `Foo`. `module M` re-exports everything imported as `M`, or imported from
+/// `M` without an alias; naming the module itself exports all of its own declarations.
+pub fn public_decls(
+    db: &dyn Db,
+    indexed: &IndexedModule,
+    exports: Option<&[DeclarationRef]>,
+    imported: &[(Option<ModuleId>, DeclId)],
+) -> Vec<DeclId> {
+    let Some(exports) = exports else {
+        return local_decls(db, indexed);
+    };
+    let mut result = vec![];
+    for export in exports {
+        use DeclarationRefKind::*;
+
+        match &**export {
+            Module { name } if *name == indexed.module_id => {
+                result.extend(local_decls(db, indexed));
+            }
+            Module { name: qualified_as } => {
+                result.extend(
+                    imported
+                        .iter()
+                        .filter(|(alias, decl)| match alias {
+                            Some(alias) => alias == qualified_as,
+                            None => decl.module(db) == *qualified_as,
+                        })
+                        .map(|(_, decl)| *decl),
+                );
+            }
+            Type {
+                name,
+                constructors: Some(DeclarationRefConstructors::All),
+            } => {
+                result.push(DeclId::new(db, Namespace::Type, indexed.module_id, *name));
+                result.extend(data_constructor_decls(db, indexed, *name));
+            }
+            x => result.extend(to_decls_id(db, indexed.module_id, x)),
+        }
+    }
+    let mut seen = HashSet::new();
+    result.retain(|decl| seen.insert(*decl));
+    result
 }
 
-impl<'a> ExportedDeclExtractor<'a> {
-    fn extract(&mut self, module: &ParsedModule, indexed: &IndexedModule) {
-        let db = self.db;
-        match &module.ast.exports {
-            Some(decl_ref_kind) => {
-                for ref_decl in decl_ref_kind {
-                    use DeclarationRefKind::*;
-
-                    match &**ref_decl {
-                        Module { name: qualified_as } => {
-                            self.exported_decls.extend(
-                                self.imported_decls
-                                    .iter()
-                                    .filter(|(name, _)| {
-                                        name.is_some_and(|name| name == *qualified_as)
-                                    })
-                                    .map(|(_, decl)| *decl),
-                            );
-                        }
-                        x => {
-                            self.exported_decls
-                                .extend(to_decls_id(db, self.module_id, x));
+/// Checks that every operator in the export list has a fixity declaration in the module, in the
+/// same namespace, and that an unqualified target of that declaration is declared in the module.
+/// Operators imported unqualified are re-exported as they are.
+pub fn check_operator_exports(
+    db: &dyn Db,
+    module: &Module,
+    imported: &[(Option<ModuleId>, DeclId)],
+) -> Vec<RenameError> {
+    let Some(exports) = &module.exports else {
+        return vec![];
+    };
+    let mut values = HashSet::new();
+    let mut types = HashSet::new();
+    for decl in &module.declarations {
+        match &decl.1 .1 {
+            DeclarationKind::ValueDeclaration(value) => {
+                values.insert(value.ident);
+            }
+            DeclarationKind::ForeignValue { name, .. } => {
+                values.insert(*name);
+            }
+            DeclarationKind::Data {
+                name, constructors, ..
+            } => {
+                types.insert(*name);
+                values.extend(constructors.iter().map(|constructor| constructor.1 .1.name));
+            }
+            DeclarationKind::TypeSynonym { name, .. } => {
+                types.insert(*name);
+            }
+            DeclarationKind::Class(class) => {
+                types.insert(class.name);
+                values.extend(class.methods.iter().map(|method| method.ident));
+            }
+            _ => {}
+        }
+    }
+
+    let mut errors = vec![];
+    for export in exports {
+        let (exported, is_type_op) = match &export.1 {
+            DeclarationRefKind::ValueOp { name } => (*name, false),
+            DeclarationRefKind::TypeOp { name } => (*name, true),
+            _ => continue,
+        };
+        let namespace = if is_type_op {
+            Namespace::Type
+        } else {
+            Namespace::Value
+        };
+        let is_imported = imported.iter().any(|(alias, decl)| {
+            alias.is_none() && decl.namespace(db) == namespace && decl.name(db) == exported
+        });
+        if is_imported {
+            continue;
+        }
+        let target = module
+            .declarations
+            .iter()
+            .find_map(|decl| match &decl.1 .1 {
+                DeclarationKind::Operator { name, operator, .. } if *operator == exported => {
+                    match name {
+                        OperatorTarget::Type(target) if is_type_op => Some((target, &types)),
+                        OperatorTarget::Value(target) | OperatorTarget::DataConstructor(target)
+                            if !is_type_op =>
+                        {
+                            Some((target, &values))
                         }
+                        _ => None,
                     }
                 }
-            }
-            None => {
-                for abs_name in indexed.values.keys() {
-                    self.exported_decls.push(DeclId::new(
-                        db,
-                        Namespace::Value,
-                        indexed.module_id,
-                        abs_name.name(db),
-                    ));
-                }
+                _ => None,
+            });
+        let declared = match target {
+            None => false,
+            // Qualified targets are imported, and resolved like any other imported name
+            Some((target, _)) if target.module(db).is_some() => true,
+            Some((target, declared)) => declared.contains(&target.name(db)),
+        };
+        if !declared {
+            errors.push(RenameError::UnknownExport(
+                export.0,
+                exported.text(db).clone(),
+            ));
+        }
+    }
+    errors
+}
 
-                for abs_name in indexed.types.keys() {
-                    self.exported_decls.push(DeclId::new(
-                        db,
-                        Namespace::Type,
-                        indexed.module_id,
-                        abs_name.name(db),
-                    ));
-                }
+/// Constructors of the data type `name` declared in the module, as `Foo(..)` refers to them.
+fn data_constructor_decls(db: &dyn Db, indexed: &IndexedModule, name: Symbol) -> Vec<DeclId> {
+    let abs_name = AbsoluteName::new(db, indexed.module_id, name);
+    match indexed.types.get(&abs_name) {
+        Some(TypeDecl::Data(data)) => data
+            .constructors
+            .iter()
+            .map(|constructor| {
+                DeclId::new(
+                    db,
+                    Namespace::Value,
+                    indexed.module_id,
+                    constructor.1 .1.name,
+                )
+            })
+            .collect(),
+        _ => vec![],
+    }
+}
 
-                for abs_name in indexed.classes.keys() {
-                    self.exported_decls.push(DeclId::new(
-                        db,
-                        Namespace::Class,
-                        indexed.module_id,
-                        abs_name.name(db),
-                    ));
-                }
+/// All declarations of the module, as exported when there's no export list.
+fn local_decls(db: &dyn Db, indexed: &IndexedModule) -> Vec<DeclId> {
+    let mut decls = vec![];
+    for abs_name in indexed.values.keys() {
+        decls.push(DeclId::new(
+            db,
+            Namespace::Value,
+            indexed.module_id,
+            abs_name.name(db),
+        ));
+    }
+
+    for (abs_name, type_decl) in indexed.types.iter() {
+        decls.push(DeclId::new(
+            db,
+            Namespace::Type,
+            indexed.module_id,
+            abs_name.name(db),
+        ));
+
+        if let TypeDecl::Data(data) = type_decl {
+            for constructor in &data.constructors {
+                decls.push(DeclId::new(
+                    db,
+                    Namespace::Value,
+                    indexed.module_id,
+                    constructor.1 .1.name,
+                ));
             }
         }
     }
+
+    for (abs_name, class) in indexed.classes.iter() {
+        decls.push(DeclId::new(
+            db,
+            Namespace::Class,
+            indexed.module_id,
+            abs_name.name(db),
+        ));
+
+        // Class methods are resolved like ordinary values
+        for method in &class.methods {
+            decls.push(DeclId::new(
+                db,
+                Namespace::Value,
+                indexed.module_id,
+                method.ident,
+            ));
+        }
+    }
+
+    decls.extend(indexed.operators.keys().copied());
+    decls
 }
 
 #[salsa::tracked]
@@ -213,18 +362,18 @@ pub fn exported_decls(db: &dyn Db, module_id: ModuleId) -> Vec<DeclId> {
     let module = crate::parsed_module(db, module_id);
     let imported = imported_decls(db, module_id);
 
-    let mut extractor = ExportedDeclExtractor {
-        db,
-        module_id,
-        exported_decls: Default::default(),
-        imported_decls: imported,
-    };
-
-    extractor.extract(&module, &indexed);
-
-    extractor.exported_decls
+    public_decls(db, &indexed, module.ast.exports.as_deref(), &imported)
 }
 
+/// Resolves the import declarations of a module into the declarations they bring into scope,
+/// each with the alias it's accessible under (`None` for unqualified access).
+///
+/// - `import M` brings everything `M` exports into scope unqualified
+/// - `import M (x, T(..))` brings only the listed names, expanding `T(..)` to its constructors
+/// - `import M hiding (x)` brings everything except the listed names
+/// - `import M as Q` and `import M (x) as Q` make the names accessible only as `Q.x`
+///
+/// `Prim` is imported implicitly, unless the module imports it explicitly.
 pub fn imported_decls(db: &dyn Db, module_id: ModuleId) -> Vec<(Option<ModuleId>, DeclId)> {
     let module = crate::parsed_module(db, module_id);
 
@@ -245,35 +394,120 @@ pub fn imported_decls(db: &dyn Db, module_id: ModuleId) -> Vec<(Option<ModuleId>
     }
 
     for import in &module.ast.imports {
-        use ImportDeclarationKind::*;
-        match &import.kind {
-            Implicit => {
-                crate::renamed_module::exported_decls(db, import.module)
-                    .iter()
-                    .for_each(|i| imports.push((import.alias, *i)));
-            }
-            Explicit(decls) => {
-                decls
-                    .iter()
-                    .flat_map(|i| to_decls_id(db, import.module, i))
-                    .for_each(|i| imports.push((import.alias, i)));
-            }
-            Hiding(decls) => {
-                let excluded: HashSet<DeclId> = decls
-                    .iter()
-                    .map(|i| to_decl_id(db, import.module, i))
-                    .collect();
-                crate::renamed_module::exported_decls(db, import.module)
-                    .iter()
-                    .filter(|i| !excluded.contains(i))
-                    .for_each(|i| imports.push((import.alias, *i)));
-            }
-        }
+        imports.extend(
+            import_decls(db, import)
+                .into_iter()
+                .map(|decl| (import.alias, decl)),
+        );
     }
 
     imports
 }
 
+/// Declarations brought into scope by a single import declaration.
+fn import_decls(db: &dyn Db, import: &ImportInner) -> Vec<DeclId> {
+    use ImportDeclarationKind::*;
+    match &import.kind {
+        Implicit => exported_decls(db, import.module),
+        Explicit(decls) => decls
+            .iter()
+            .flat_map(|i| to_decls_id(db, import.module, i))
+            .collect(),
+        Hiding(decls) => {
+            let excluded: HashSet<DeclId> = decls
+                .iter()
+                .flat_map(|i| to_decls_id(db, import.module, i))
+                .collect();
+            exported_decls(db, import.module)
+                .into_iter()
+                .filter(|i| !excluded.contains(i))
+                .collect()
+        }
+    }
+}
+
+/// An import that the module could do without.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum UnusedImport {
+    /// None of the names brought into scope by the import are used
+    Import(SourceSpan, String),
+    /// A name in an explicit import list is never used
+    Name(SourceSpan, String),
+}
+
+impl UnusedImport {
+    pub fn span(&self) -> SourceSpan {
+        match self {
+            Self::Import(span, _) | Self::Name(span, _) => *span,
+        }
+    }
+
+    pub fn message(&self) -> String {
+        match self {
+            Self::Import(_, module) => format!("Unused import of module '{}'", module),
+            Self::Name(_, name) => format!("Unused imported name '{}'", name),
+        }
+    }
+}
+
+impl From<UnusedImport> for Diagnostic {
+    fn from(unused: UnusedImport) -> Self {
+        Diagnostic {
+            severity: Severity::Warning,
+            ..Diagnostic::new(unused.span(), unused.message())
+        }
+    }
+}
+
+/// Finds imports whose names are never referred to in the module, after renaming.
+///
+/// An import is unused as a whole if none of the names it brings into scope are used; otherwise,
+/// each name of an explicit import list that's never used is reported on its own. `import M ()`
+/// brings in only instances, so it's never reported.
+pub fn unused_imports(db: &dyn Db, module_id: ModuleId) -> Vec<UnusedImport> {
+    let module = crate::parsed_module(db, module_id);
+    let renamed = renamed_module(db, module_id);
+    let is_used = |alias: Option<ModuleId>, decl: &DeclId| {
+        renamed
+            .used_names
+            .contains(&(alias, decl.to_absolute_name(db)))
+    };
+
+    let mut unused = vec![];
+    for import in &module.ast.imports {
+        if matches!(&import.kind, ImportDeclarationKind::Explicit(refs) if refs.is_empty()) {
+            continue;
+        }
+        let decls = import_decls(db, import);
+        if !decls.iter().any(|decl| is_used(import.alias, decl)) {
+            unused.push(UnusedImport::Import(
+                import.0,
+                import.module.name(db).clone(),
+            ));
+            continue;
+        }
+        if let ImportDeclarationKind::Explicit(refs) = &import.kind {
+            for r in refs {
+                use DeclarationRefKind::*;
+                let name = match &r.1 {
+                    TypeClass { name } => format!("class {}", name.text(db)),
+                    TypeOp { name } => format!("type ({})", name.text(db)),
+                    Type { name, .. } | Value { name } | TypeInstanceRef { name, .. } => {
+                        name.text(db).clone()
+                    }
+                    ValueOp { name } => format!("({})", name.text(db)),
+                    Module { .. } => continue,
+                };
+                let decls = to_decls_id(db, import.module, r);
+                if !decls.iter().any(|decl| is_used(import.alias, decl)) {
+                    unused.push(UnusedImport::Name(r.0, name));
+                }
+            }
+        }
+    }
+    unused
+}
+
 fn to_decls_id(db: &dyn Db, module_id: ModuleId, kind: &DeclarationRefKind) -> Vec<DeclId> {
     use DeclarationRefKind::*;
 
@@ -293,7 +527,10 @@ fn to_decls_id(db: &dyn Db, module_id: ModuleId, kind: &DeclarationRefKind) -> V
                             ));
                         }
                     }
-                    DeclarationRefConstructors::All => {}
+                    DeclarationRefConstructors::All => {
+                        let indexed = crate::indexed_module::indexed_module(db, module_id);
+                        decls.extend(data_constructor_decls(db, &indexed, *name));
+                    }
                 }
             }
             decls
@@ -392,6 +629,83 @@ mod tests {
         ))
     }
 
+    #[test]
+    fn export_explicit_values() {
+        assert_snapshot!(export_decls(
+            indoc!(
+                "
+    module Test (x, Foo) where
+    data Foo = Bar
+    x = 1
+    y = 2
+    "
+            ),
+            vec![]
+        ))
+    }
+
+    #[test]
+    fn export_all_constructors() {
+        assert_snapshot!(export_decls(
+            indoc!(
+                "
+    module Test (Foo(..)) where
+    data Foo = Bar | Baz
+    data Quux = Quux
+    "
+            ),
+            vec![]
+        ))
+    }
+
+    #[test]
+    fn reexport_unaliased_import() {
+        assert_snapshot!(export_decls(
+            indoc!(
+                "
+    module Test (module Lib2) where
+    import Lib2 (y)
+    "
+            ),
+            vec![LIB2]
+        ))
+    }
+
+    #[test]
+    fn renamed_values_are_resolved() {
+        let db = &mut crate::Database::test_single_file_db(indoc!(
+            "
+            module Test where
+            import Lib2
+            z = x
+            "
+        ));
+        db.add_source_file("Lib2.purs".into(), LIB2.into()).unwrap();
+        let module_id = ModuleId::new(db, "Test".into());
+
+        let module = renamed_module(db, module_id);
+
+        let z = module
+            .values
+            .get(&AbsoluteName::new(
+                db,
+                module_id,
+                Symbol::new(db, "z".into()),
+            ))
+            .unwrap();
+        let crate::ast::PossiblyGuardedExpr::Unconditional(expr) = &z.equations[0].expr else {
+            panic!("expected unguarded equation")
+        };
+        let crate::ast::ExprKind::Var(name) = &expr.1 else {
+            panic!("expected variable")
+        };
+        assert_eq!(
+            name.module(db).map(|module| module.name(db).clone()),
+            Some("Lib2".to_string())
+        );
+        assert_eq!(name.name(db).text(db), "x");
+    }
+
     #[test]
     fn export_blanket_data_decl() {
         assert_snapshot!(export_decls(
@@ -473,13 +787,55 @@ mod tests {
                 "
         module Test where
         import Prim ()    
-        import Foo (Foo(..))
+        import Lib (Foo(..))
+        "
+            ),
+            vec![LIB1]
+        ))
+    }
+
+    #[test]
+    fn import_hiding() {
+        assert_snapshot!(import_decls(
+            indoc!(
+                "
+        module Test where
+        import Prim ()
+        import Lib2 hiding (x)
+        "
+            ),
+            vec![LIB2]
+        ))
+    }
+
+    #[test]
+    fn import_hiding_constructors() {
+        assert_snapshot!(import_decls(
+            indoc!(
+                "
+        module Test where
+        import Prim ()
+        import Lib hiding (Foo(..))
         "
             ),
             vec![LIB1]
         ))
     }
 
+    #[test]
+    fn import_qualified_subset() {
+        assert_snapshot!(import_decls(
+            indoc!(
+                "
+        module Test where
+        import Prim ()
+        import Lib2 (y) as Q
+        "
+            ),
+            vec![LIB2]
+        ))
+    }
+
     #[test]
     fn import_data_qualified_decl() {
         assert_snapshot!(import_decls(
@@ -570,4 +926,157 @@ mod tests {
             vec![]
         ))
     }
+
+    #[test]
+    fn unused_imports_are_reported() {
+        let input = indoc!(
+            "
+            module Test where
+            import Unused (a)
+            import Unused ()
+            import Lib2 (x, y)
+            import Lib2 as L
+            z = [x, L.y]
+            "
+        );
+        let db = &mut crate::Database::test_single_file_db(input);
+        db.add_source_file("Unused.purs".into(), "module Unused where\na = 1\n".into())
+            .unwrap();
+        db.add_source_file("Lib2.purs".into(), LIB2.into()).unwrap();
+        let module_id = ModuleId::new(db, "Test".into());
+
+        let unused = unused_imports(db, module_id)
+            .into_iter()
+            .map(|unused| {
+                let span = unused.span();
+                let diagnostic = Diagnostic::from(unused);
+                (
+                    diagnostic.severity,
+                    diagnostic.message,
+                    &input[span.start..span.end],
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            unused,
+            vec![
+                (
+                    Severity::Warning,
+                    "Unused import of module 'Unused'".to_string(),
+                    "import Unused (a)"
+                ),
+                (
+                    Severity::Warning,
+                    "Unused imported name 'y'".to_string(),
+                    "y"
+                ),
+            ]
+        );
+    }
+
+    fn operator_export_errors(input: &str, deps: Vec<&str>) -> Vec<String> {
+        let db = &mut crate::Database::test_single_file_db(input);
+        deps.into_iter().zip(1..).for_each(|(dep, i)| {
+            db.add_source_file(format!("Lib{}.purs", i).into(), dep.into())
+                .unwrap();
+        });
+        let module_id = ModuleId::new(db, "Test".into());
+        let module = crate::parsed_module(db, module_id);
+        let imported = imported_decls(db, module_id);
+        check_operator_exports(db, &module.ast, &imported)
+            .into_iter()
+            .map(|err| err.message())
+            .collect()
+    }
+
+    #[test]
+    fn export_operator_without_fixity() {
+        let input = indoc!(
+            "
+            module Test ((+~), type (<>)) where
+            add x y = x
+            "
+        );
+        assert_eq!(
+            operator_export_errors(input, vec![]),
+            vec!["Unknown export '+~'", "Unknown export '<>'"]
+        );
+    }
+
+    #[test]
+    fn export_operator_with_fixity() {
+        let input = indoc!(
+            "
+            module Test ((+~), (:|), type (<>)) where
+            data NonEmpty a = NonEmpty a
+            type Append a b = b
+            add x y = x
+            infixl 6 add as +~
+            infixr 5 NonEmpty as :|
+            infixr 5 type Append as <>
+            "
+        );
+        assert_eq!(operator_export_errors(input, vec![]), Vec::<String>::new());
+    }
+
+    #[test]
+    fn export_operator_with_undeclared_target() {
+        let input = indoc!(
+            "
+            module Test ((+~), type (<>)) where
+            infixl 6 add as +~
+            infixl 6 add as <>
+            "
+        );
+        assert_eq!(
+            operator_export_errors(input, vec![]),
+            vec!["Unknown export '+~'", "Unknown export '<>'"]
+        );
+    }
+
+    #[test]
+    fn export_imported_operator() {
+        let input = indoc!(
+            "
+            module Test ((<>), type (~>)) where
+            import Lib1 ((<>), type (~>))
+            "
+        );
+        let lib = indoc!(
+            "
+            module Lib1 ((<>), type (~>)) where
+            type Fn a b = a
+            append x y = x
+            infixr 5 append as <>
+            infixr 4 type Fn as ~>
+            "
+        );
+        assert_eq!(
+            operator_export_errors(input, vec![lib]),
+            Vec::<String>::new()
+        );
+    }
+
+    #[test]
+    fn export_operator_imported_implicitly() {
+        let input = indoc!(
+            "
+            module Test ((<>), type (~>)) where
+            import Lib1
+            "
+        );
+        let lib = indoc!(
+            "
+            module Lib1 where
+            type Fn a b = a
+            append x y = x
+            infixr 5 append as <>
+            infixr 4 type Fn as ~>
+            "
+        );
+        assert_eq!(
+            operator_export_errors(input, vec![lib]),
+            Vec::<String>::new()
+        );
+    }
 }
@@ -0,0 +1,90 @@
+use crate::symbol::Symbol;
+use std::collections::HashMap;
+
+/// Nested lexical scopes, mapping each bound name to a value (e.g. the span of its binding).
+///
+/// Lookups search from the innermost scope outwards, so inner definitions shadow outer ones.
+#[derive(Clone, Debug)]
+pub struct Scope<V> {
+    scopes: Vec<HashMap<Symbol, V>>,
+}
+
+impl<V> Default for Scope<V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<V> Scope<V> {
+    /// Creates a scope stack with a single, outermost scope.
+    pub fn new() -> Self {
+        Self {
+            scopes: vec![HashMap::new()],
+        }
+    }
+
+    pub fn enter(&mut self) {
+        self.scopes.push(HashMap::new());
+    }
+
+    pub fn exit(&mut self) {
+        let scope = self.scopes.pop();
+        assert!(
+            scope.is_some(),
+            "Scope::exit called when there are no scopes"
+        );
+    }
+
+    /// Binds `name` in the innermost scope. Returns the previous value if the name was already
+    /// bound in that same scope.
+    pub fn define(&mut self, name: Symbol, value: V) -> Option<V> {
+        self.scopes
+            .last_mut()
+            .expect("Scope::define called when there are no scopes")
+            .insert(name, value)
+    }
+
+    pub fn lookup(&self, name: Symbol) -> Option<&V> {
+        self.scopes.iter().rev().find_map(|scope| scope.get(&name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nested_define_and_lookup() {
+        let db = &crate::Database::new();
+        let x = Symbol::new(db, "x".into());
+        let y = Symbol::new(db, "y".into());
+        let mut scope = Scope::new();
+
+        scope.define(x, 1);
+        scope.enter();
+        assert_eq!(scope.define(x, 2), None);
+        assert_eq!(scope.define(x, 3), Some(2));
+        scope.define(y, 4);
+        assert_eq!(scope.lookup(x), Some(&3));
+        assert_eq!(scope.lookup(y), Some(&4));
+
+        scope.exit();
+        assert_eq!(scope.lookup(x), Some(&1));
+        assert_eq!(scope.lookup(y), None);
+    }
+
+    #[test]
+    fn lookup_searches_outwards() {
+        let db = &crate::Database::new();
+        let x = Symbol::new(db, "x".into());
+        let mut scope = Scope::new();
+
+        scope.define(x, "outer");
+        scope.enter();
+        scope.enter();
+        assert_eq!(scope.lookup(x), Some(&"outer"));
+        scope.exit();
+        scope.exit();
+        assert_eq!(scope.lookup(x), Some(&"outer"));
+    }
+}
@@ -0,0 +1,166 @@
+//! Serde support for the AST, enabled by the `serde` feature.
+//!
+//! Interned values (symbols, module names, qualified names) are written in their string form, so
+//! looking them up needs a database. Serialization and deserialization have to run inside
+//! [`with_db`].
+
+use crate::ast::QualifiedName;
+use crate::renamed_module::{DeclId, Namespace};
+use crate::symbol::Symbol;
+use crate::{Database, ModuleId};
+use salsa::ParallelDatabase;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::cell::RefCell;
+
+thread_local! {
+    static DB: RefCell<Option<salsa::Snapshot<Database>>> = const { RefCell::new(None) };
+}
+
+/// Runs `f` with `db` available to the serde impls of interned values.
+pub fn with_db<R>(db: &Database, f: impl FnOnce() -> R) -> R {
+    let previous = DB.with(|cell| cell.replace(Some(db.snapshot())));
+    let result = f();
+    DB.with(|cell| *cell.borrow_mut() = previous);
+    result
+}
+
+fn current_db<R>(f: impl FnOnce(&Database) -> R) -> R {
+    DB.with(|cell| {
+        let db = cell.borrow();
+        f(db.as_ref()
+            .expect("AST serialized outside of `serialize::with_db`"))
+    })
+}
+
+impl Serialize for Symbol {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        current_db(|db| self.text(db).clone()).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Symbol {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        Ok(current_db(|db| Symbol::new(db, text)))
+    }
+}
+
+impl Serialize for ModuleId {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        current_db(|db| self.name(db)).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for ModuleId {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        Ok(current_db(|db| ModuleId::new(db, name)))
+    }
+}
+
+/// Written as in source, e.g. `Data.Maybe.Just` or `x`
+impl Serialize for QualifiedName {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        current_db(|db| match self.module(db) {
+            Some(module) => format!("{}.{}", module.name(db), self.name(db).text(db)),
+            None => self.name(db).text(db).clone(),
+        })
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for QualifiedName {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        let (module, name) = split_qualified(&text);
+        Ok(current_db(|db| {
+            let name = Symbol::new(db, name.into());
+            match module {
+                Some(module) => {
+                    QualifiedName::new_qualified(db, ModuleId::new(db, module.into()), name)
+                }
+                None => QualifiedName::new(db, None, name),
+            }
+        }))
+    }
+}
+
+/// Splits `Data.Maybe.Just` into `Data.Maybe` and `Just`. Every capitalized segment followed by a
+/// dot belongs to the module name, so that qualified operators like `Prelude..` split correctly.
+fn split_qualified(text: &str) -> (Option<&str>, &str) {
+    let mut module_end = None;
+    let mut pos = 0;
+    while text[pos..].starts_with(char::is_uppercase) {
+        let segment_end = text[pos..]
+            .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '\''))
+            .map_or(text.len(), |i| pos + i);
+        if !text[segment_end..].starts_with('.') || segment_end + 1 == text.len() {
+            break;
+        }
+        module_end = Some(segment_end);
+        pos = segment_end + 1;
+    }
+    match module_end {
+        Some(end) => (Some(&text[..end]), &text[end + 1..]),
+        None => (None, text),
+    }
+}
+
+/// Written as a `[namespace, module, name]` triple
+impl Serialize for DeclId {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        current_db(|db| (self.namespace(db), self.module(db), self.name(db))).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for DeclId {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let (namespace, module, name) = <(Namespace, ModuleId, Symbol)>::deserialize(deserializer)?;
+        Ok(current_db(|db| DeclId::new(db, namespace, module, name)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::Expr;
+    use crate::utils::tests::dummy_module;
+    use indoc::indoc;
+    use salsa::DebugWithDb;
+
+    #[test]
+    fn test_split_qualified() {
+        assert_eq!(split_qualified("x"), (None, "x"));
+        assert_eq!(split_qualified("Maybe"), (None, "Maybe"));
+        assert_eq!(
+            split_qualified("Data.Maybe.Just"),
+            (Some("Data.Maybe"), "Just")
+        );
+        assert_eq!(split_qualified("Prelude.."), (Some("Prelude"), "."));
+        assert_eq!(split_qualified("<<<"), (None, "<<<"));
+    }
+
+    #[test]
+    fn test_expr_round_trip() {
+        let db = &crate::Database::new();
+        let module = dummy_module(db);
+        let input = indoc!(
+            r#"
+            \x -> case Data.Maybe.fromMaybe 1.5 x of
+              Data.Maybe.Just a | a > 0 -> ({ l: a } :: forall r. { l :: Int | r }) <> "s"
+              _ -> (x Prelude.<<< f @Int) 'c'
+            "#
+        );
+        let (errors, expr) = crate::parser::parse_expr(db, input, module);
+        assert_eq!(errors, vec![]);
+        let expr = expr.unwrap();
+
+        let json = with_db(db, || serde_json::to_string(&expr)).unwrap();
+        let deserialized: Expr = with_db(db, || serde_json::from_str(&json)).unwrap();
+
+        assert_eq!(
+            format!("{:#?}", deserialized.debug(db)),
+            format!("{:#?}", expr.debug(db))
+        );
+    }
+}
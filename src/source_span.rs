@@ -8,6 +8,7 @@ use crate::{
     ModuleId,
 };
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Eq, PartialEq, Debug, Hash, Clone, Copy, DebugWithDb, new)]
 pub struct SourceSpan {
     pub decl: SpanDeclRef,
@@ -15,6 +16,7 @@ pub struct SourceSpan {
     pub end: usize,
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Eq, PartialEq, Debug, Hash, Clone, Copy, DebugWithDb)]
 pub enum SpanDeclRef {
     Module(ModuleId),
@@ -100,6 +102,45 @@ impl SourceSpan {
     }
 }
 
+/// Maps byte offsets in a source file to 1-based line and column numbers. Columns count
+/// characters, not bytes.
+pub struct LineIndex<'a> {
+    source: &'a str,
+    /// Offset of the start of each line
+    line_starts: Vec<usize>,
+}
+
+impl<'a> LineIndex<'a> {
+    pub fn new(source: &'a str) -> Self {
+        let line_starts = std::iter::once(0)
+            .chain(source.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+        Self {
+            source,
+            line_starts,
+        }
+    }
+
+    /// Line and column of the character at `offset`. Offsets past the end of the source are
+    /// treated as the end of the source.
+    pub fn line_col(&self, offset: usize) -> (u32, u32) {
+        let offset = offset.min(self.source.len());
+        let line = self.line_starts.partition_point(|&start| start <= offset) - 1;
+        let line_start = self.line_starts[line];
+        let column = self.source[line_start..]
+            .char_indices()
+            .take_while(|(i, _)| line_start + i < offset)
+            .count();
+        (line as u32 + 1, column as u32 + 1)
+    }
+
+    /// Line and column of the start and end of the span, which has to be relative to the start of
+    /// this source (see `SourceSpanOps::to_absolute_span`).
+    pub fn span_line_cols(&self, span: &SourceSpan) -> ((u32, u32), (u32, u32)) {
+        (self.line_col(span.start), self.line_col(span.end))
+    }
+}
+
 pub trait ToSourceSpan {
     fn to_source_span(&self, module_id: ModuleId) -> SourceSpan;
 }
@@ -180,3 +221,45 @@ impl SourceSpanOps for CaseBranch {
         self
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn line_col() {
+        let source = "ab\nλx → y\n\nz";
+        let index = LineIndex::new(source);
+        // `λ` is two bytes long, `→` three
+        let positions = [0, 1, 2, 3, 5, 6, 7, 10, 11, 12, 13, 14, source.len(), 100]
+            .map(|offset| index.line_col(offset));
+        assert_eq!(
+            positions,
+            [
+                (1, 1),
+                (1, 2),
+                (1, 3),
+                (2, 1),
+                (2, 2),
+                (2, 3),
+                (2, 4),
+                (2, 5),
+                (2, 6),
+                (2, 7),
+                (3, 1),
+                (4, 1),
+                (4, 2),
+                (4, 2),
+            ]
+        );
+    }
+
+    #[test]
+    fn span_line_cols() {
+        let source = "module Test where\nx = \"é\"\n";
+        let index = LineIndex::new(source);
+        let start = source.find('"').unwrap();
+        let span = SourceSpan::new(SpanDeclRef::Unknown, start, start + "\"é\"".len());
+        assert_eq!(index.span_line_cols(&span), ((2, 5), (2, 8)));
+    }
+}
@@ -0,0 +1,66 @@
+use crate::ast::visit::{walk_expr, Visitor};
+use crate::ast::{Expr, ExprKind, Literal, Module};
+use crate::source_span::SourceSpan;
+
+/// Collects every string literal occurring in expressions of the module, in source order.
+pub fn collect_string_literals(module: &Module) -> Vec<(String, SourceSpan)> {
+    let mut collector = StringLiteralCollector::default();
+    collector.visit_module(module);
+    collector.literals
+}
+
+#[derive(Default)]
+struct StringLiteralCollector {
+    literals: Vec<(String, SourceSpan)>,
+}
+
+impl Visitor for StringLiteralCollector {
+    fn visit_expr(&mut self, expr: &Expr) {
+        if let ExprKind::Literal(Literal::String(s)) = &expr.1 {
+            self.literals.push((s.to_string_lossy(), expr.0));
+        }
+        walk_expr(self, expr);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::tests::parse_module_id;
+    use indoc::indoc;
+
+    fn collect(input: &str) -> Vec<(String, usize, usize)> {
+        let db = crate::Database::new();
+        let module_id = parse_module_id(input, &db);
+        let (errors, result) = crate::parser::parse_module(&db, input, module_id);
+        assert_eq!(errors, vec![]);
+        collect_string_literals(&result.unwrap())
+            .into_iter()
+            .map(|(s, span)| (s, span.start, span.end))
+            .collect()
+    }
+
+    #[test]
+    fn test_collect_string_literals() {
+        let input = indoc!(
+            r#"
+            module Test where
+            greeting = "hello"
+            main = do
+              log "in do"
+              let x = { label: "in record" }
+              case x of
+                _ | true -> ["in case"]
+            "#
+        );
+        let expected = ["hello", "in do", "in record", "in case"]
+            .iter()
+            .map(|s| {
+                let literal = format!("\"{}\"", s);
+                let start = input.find(&literal).unwrap();
+                (s.to_string(), start, start + literal.len())
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(collect(input), expected);
+    }
+}
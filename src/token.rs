@@ -4,7 +4,7 @@ use std::fmt::Display;
 
 #[derive(PartialEq, Eq, Debug, Clone)]
 pub enum Token {
-    IntegerLiteral(u64),
+    IntegerLiteral(i64),
     FloatLiteral(String),
     StringLiteral(PSString),
     CharLiteral(PSChar),
@@ -12,6 +12,8 @@ pub enum Token {
     QualifiedLowerIdentifier((String, String)),
     UpperIdentifier(String),
     QualifiedUpperIdentifier((String, String)),
+    /// Typed hole, `?name`
+    Hole(String),
 
     // Layout
     LayoutStart,
@@ -34,6 +36,8 @@ pub enum Token {
     Dot,
     Backslash,
     At,
+    /// `@` before a visible type argument, e.g. `read @Int`
+    TypeApp,
     Minus,
 
     Arrow,        // ->
@@ -52,6 +56,8 @@ pub enum Token {
     Else,
     Ado,
     Do,
+    QualifiedAdo(String), // M.ado
+    QualifiedDo(String),  // M.do
     Case,
     Of,
     Let,
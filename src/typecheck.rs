@@ -106,7 +106,17 @@ fn to_expr(d: ValueDecl) -> Expr {
         [CaseBranch {
             pats,
             expr: PossiblyGuardedExpr::Unconditional(expr),
-        }] if pats.is_empty() => expr.clone(), // FIXME: clone
+            where_,
+        }] if pats.is_empty() => match &where_[..] {
+            [] => expr.clone(), // FIXME: clone
+            decls => Located(
+                expr.0,
+                Let {
+                    decls: decls.to_vec(),
+                    body: Box::new(expr.clone()),
+                },
+            ),
+        },
         _ => panic!("ValueDecl not desugared properly"),
     }
 }
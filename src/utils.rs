@@ -41,4 +41,29 @@ pub mod tests {
     pub fn dummy_module(db: &dyn crate::Db) -> crate::ModuleId {
         crate::ModuleId::new(db, "Test".into())
     }
+
+    /// Parses `input`, prints the result and parses the output again, expecting the same AST
+    /// without spans. Returns the printed source.
+    pub fn assert_print_reparses<T, R, E>(
+        input: &str,
+        parse: impl Fn(&str) -> (Vec<R>, Result<T, E>),
+        print: impl Fn(&T) -> String,
+    ) -> String
+    where
+        T: crate::ast::normalize::StripSpans + PartialEq + std::fmt::Debug,
+        R: std::fmt::Debug,
+        E: std::fmt::Debug,
+    {
+        let parse = |input: &str| {
+            let (errors, result) = parse(input);
+            assert!(errors.is_empty(), "{:?} in {:?}", errors, input);
+            let mut result = result.unwrap_or_else(|err| panic!("{:?} in {:?}", err, input));
+            result.strip_spans();
+            result
+        };
+        let parsed = parse(input);
+        let printed = print(&parsed);
+        assert_eq!(parse(&printed), parsed, "printed as:\n{}", printed);
+        printed
+    }
 }